@@ -0,0 +1,114 @@
+//! Load time of a 2,000-page project, as a year of scanned bills adds up to.
+//!
+//! Run with `cargo bench --bench load`.
+
+use std::{fmt::Write, hint::black_box, time::Instant};
+
+use scantailor_project::load::{load, Mode};
+
+const PAGES: u32 = 2_000;
+const RUNS: u32 = 10;
+
+const OUTLINE: &str = r#"<point x="0" y="0"/><point x="4961" y="0"/><point x="4961" y="7016"/><point x="0" y="7016"/><point x="0" y="0"/>"#;
+const COLOR_PARAMS: &str = r#"<color-params colorMode="colorOrGray"><color-or-grayscale fillMargins="1" fillOffcut="1" fillingColor="background" normalizeIlluminationColor="0"><posterization-options enabled="0" forceBlackAndWhite="1" level="4" normalizationEnabled="0"/></color-or-grayscale><bw binarizationMethod="otsu" morphologicalSmoothing="1" normalizeIlluminationBW="1" sauvolaCoef="0.34" savitzkyGolaySmoothing="1" thresholdAdj="0" windowSize="200" wolfCoef="0.3" wolfLowerBound="1" wolfUpperBound="254"><color-segmenter-options blueThresholdAdjustment="0" enabled="0" greenThresholdAdjustment="0" noiseReduction="7" redThresholdAdjustment="0"/></bw></color-params><splitting originalBackground="0" splitOutput="0" splittingMode="bw"/>"#;
+const OPTIONS: &str = r#"<picture-shape-options higherSearchSensitivity="0" pictureShape="free" sensitivity="100"/><dewarping-options mode="off" postDeskew="1" postDeskewAngle="0"/>"#;
+const PROCESSING_PARAMS: &str =
+    r#"<processing-params autoZonesFound="0" blackOnWhiteSetManually="0"/>"#;
+
+/// Builds a project in which every file is a single page scan, with ids
+/// allotted the way ScanTailor does: file, image, then page.
+fn project(pages: u32) -> String {
+    let ids = (0..pages).map(|n| (3 * n + 2, 3 * n + 3, 3 * n + 4));
+
+    let mut files = String::new();
+    let mut images = String::new();
+    let mut page_list = String::new();
+    let mut mappings = String::new();
+    let mut fix_orientation = (String::new(), String::new());
+    let mut page_split = String::new();
+    let mut deskew = (String::new(), String::new());
+    let mut select_content = String::new();
+    let mut page_layout = String::new();
+    let mut output = String::new();
+
+    for (file, image, page) in ids {
+        write!(
+            files,
+            r#"<file dirId="1" id="{file}" name="{file:06}.jpg"/>"#
+        )
+        .unwrap();
+        write!(images, r#"<image fileId="{file}" fileImage="0" id="{image}" subPages="1"><size height="4961" width="7016"/><dpi horizontal="600" vertical="600"/></image>"#).unwrap();
+        write!(
+            page_list,
+            r#"<page id="{page}" imageId="{image}" subPage="single"/>"#
+        )
+        .unwrap();
+        write!(mappings, r#"<mapping file="{file}" label="0"/>"#).unwrap();
+        write!(
+            fix_orientation.0,
+            r#"<image id="{image}"><rotation degrees="90"/></image>"#
+        )
+        .unwrap();
+        write!(
+            fix_orientation.1,
+            r#"<page id="{page}"><image-params blackOnWhite="1" bwThreshold="193"/></page>"#
+        )
+        .unwrap();
+        write!(page_split, r#"<image id="{image}" layoutType="single-cut"><params mode="manual"><pages type="single-cut"><outline>{OUTLINE}</outline><cutter1><p1 x="1142.578008915305" y="66.34323922734029"/><p2 x="1142.578008915305" y="6964.399702823181"/></cutter1><cutter2><p1 x="4959.99008" y="0"/><p2 x="4959.99008" y="7015.985967999999"/></cutter2></pages><dependencies><rotation degrees="90"/><size height="4961" width="7016"/><layoutType>single-cut</layoutType></dependencies></params></image>"#).unwrap();
+        write!(deskew.0, r#"<page id="{page}"><params angle="0.125" mode="auto"><dependencies><rotation degrees="90"/><page-outline>{OUTLINE}</page-outline></dependencies></params></page>"#).unwrap();
+        write!(
+            deskew.1,
+            r#"<page id="{page}"><image-params blackOnWhite="1" bwThreshold="190"/></page>"#
+        )
+        .unwrap();
+        write!(select_content, r#"<page id="{page}"><params contentDetectionMode="auto" fineTuneCorners="0" pageDetectionMode="disabled"><content-rect height="2236" width="3328" x="116" y="364"/><page-rect height="7024.310698751963" width="3832.297441614658" x="0" y="0"/><content-size-mm height="94.65752264837863" width="140.8856151045636"/><dependencies><rotated-page-outline>{OUTLINE}</rotated-page-outline><params contentDetectionMode="auto" fineTuneCorners="0" pageDetectionMode="disabled"/></dependencies></params></page>"#).unwrap();
+        write!(page_layout, r#"<page id="{page}"><params autoMargins="0"><hardMarginsMM bottom="5" left="5" right="5" top="5"/><pageRect height="7024.310698751963" width="3832.297441614658" x="0" y="0"/><contentRect height="2236" width="3328" x="116" y="364"/><contentSizeMM height="94.65752264837863" width="140.8856151045636"/><alignment hor="center" null="0" vert="center"/></params></page>"#).unwrap();
+        write!(output, r#"<page id="{page}"><zones/><fill-zones/><params blackOnWhite="1" depthPerception="2" despeckleLevel="1">{OPTIONS}<dpi horizontal="600" vertical="600"/>{COLOR_PARAMS}</params>{PROCESSING_PARAMS}<output-params><image blackOnWhite="1" depthPerception="2" despeckleLevel="1"><size height="2476" width="3800"/><content-rect height="2236" width="3328" x="236" y="120"/><crop-area>{OUTLINE}</crop-area><partial-xform><m11>-0.00218165983433677</m11><m12>0.9999976201773518</m12><m21>-0.9999976201773518</m21><m22>-0.00218165983433677</m22></partial-xform><dpi horizontal="600" vertical="600"/>{COLOR_PARAMS}{OPTIONS}{PROCESSING_PARAMS}</image><source_file mtime="1698783384" size="2890349"/><file mtime="1699247244" size="12506634"/><zones/><fill-zones/></output-params></page>"#).unwrap();
+    }
+
+    format!(
+        concat!(
+            r#"<project layoutDirection="LTR" outputDirectory="/bills/out" version="3">"#,
+            r#"<directories><directory id="1" path="/bills"/></directories>"#,
+            "<files>{}</files><images>{}</images><pages>{}</pages>",
+            "<file-name-disambiguation>{}</file-name-disambiguation><filters>",
+            "<fix-orientation>{}<image-settings>{}</image-settings></fix-orientation>",
+            r#"<page-split defaultLayoutType="auto-detect">{}</page-split>"#,
+            "<deskew>{}<image-settings>{}</image-settings></deskew>",
+            r#"<select-content pageDetectionTolerance="0.1">{}</select-content>"#,
+            r#"<page-layout showMiddleRect="1">{}</page-layout>"#,
+            "<output>{}</output></filters></project>",
+        ),
+        files,
+        images,
+        page_list,
+        mappings,
+        fix_orientation.0,
+        fix_orientation.1,
+        page_split,
+        deskew.0,
+        deskew.1,
+        select_content,
+        page_layout,
+        output,
+    )
+}
+
+fn main() {
+    let content = project(PAGES);
+    println!(
+        "project with {} pages, {:.1} MiB",
+        PAGES,
+        content.len() as f64 / (1024f64 * 1024f64)
+    );
+
+    for (name, mode) in [("strict", Mode::Strict), ("lenient", Mode::Lenient)] {
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            let loaded = load(black_box(&content), mode).unwrap();
+            assert_eq!(loaded.project.pages.page.len(), PAGES as usize);
+            black_box(loaded);
+        }
+        println!("{} load: {:?} per run", name, start.elapsed() / RUNS);
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    hash::Hash,
+};
+
+use super::{
+    filters::EntryVisitor,
+    id::{ImageId, PageId},
+    ProjectXML,
+};
+
+/// What to do with entries of a filter section sharing an id.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub enum Duplicates {
+    /// keep the entry found first in the file
+    KeepFirst,
+    /// keep the entry found last in the file, as ScanTailor does when loading,
+    /// each entry overwriting the settings read before it
+    #[default]
+    KeepLast,
+    /// keep every entry, only reporting them
+    KeepAll,
+}
+
+/// Entries of a filter section found with the same id.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Duplicate {
+    /// path of the entries, as in `project/filters/deskew/page[id=4]`
+    pub path: String,
+    pub count: usize,
+    pub removed: usize,
+}
+
+impl Display for Duplicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} entries", self.path, self.count)?;
+        if self.removed > 0 {
+            write!(f, ", {} removed", self.removed)?;
+        }
+        Ok(())
+    }
+}
+
+/// Sorts the entries of every filter section in page order and handles entries sharing
+/// an id as asked, so that projects with the same content serialize the same.
+///
+/// Entries of images are ordered by the first page of the image. Entries of pages or
+/// images the project does not know come last, by id.
+pub fn canonicalize(project: &mut ProjectXML, duplicates: Duplicates) -> Vec<Duplicate> {
+    let mut found = vec![];
+
+    let pages = project
+        .pages
+        .page
+        .iter()
+        .enumerate()
+        .map(|(position, page)| (page.id, position))
+        .collect::<HashMap<_, _>>();
+    let mut images = HashMap::new();
+    for (position, page) in project.pages.page.iter().enumerate() {
+        images.entry(page.image_id).or_insert(position);
+    }
+    for (position, image) in project.images.image.iter().enumerate() {
+        images
+            .entry(image.id)
+            .or_insert(project.pages.page.len() + position);
+    }
+
+    project.filters.visit_entries(&mut Sort {
+        duplicates,
+        pages: &pages,
+        images: &images,
+        found: &mut found,
+    });
+
+    found
+}
+
+struct Sort<'a> {
+    duplicates: Duplicates,
+    /// position of each page, and of each image by its first page
+    pages: &'a HashMap<PageId, usize>,
+    images: &'a HashMap<ImageId, usize>,
+    found: &'a mut Vec<Duplicate>,
+}
+
+impl EntryVisitor for Sort<'_> {
+    fn images<T>(&mut self, entries: Option<&mut Vec<T>>, id: fn(&T) -> ImageId, path: &str) {
+        let order = self.images;
+        self.entries(entries, id, order, path);
+    }
+
+    fn pages<T>(&mut self, entries: Option<&mut Vec<T>>, id: fn(&T) -> PageId, path: &str) {
+        let order = self.pages;
+        self.entries(entries, id, order, path);
+    }
+}
+
+impl Sort<'_> {
+    fn entries<T, I: Copy + Ord + Hash + Display>(
+        &mut self,
+        entries: Option<&mut Vec<T>>,
+        id: fn(&T) -> I,
+        order: &HashMap<I, usize>,
+        path: &str,
+    ) {
+        let Some(entries) = entries else {
+            return;
+        };
+        // stable, so that entries sharing an id stay in file order
+        entries.sort_by_key(|entry| {
+            (
+                order.get(&id(entry)).copied().unwrap_or(usize::MAX),
+                id(entry),
+            )
+        });
+
+        let mut counts = Vec::<(I, usize)>::new();
+        for entry in entries.iter() {
+            match counts.last_mut() {
+                Some((last, count)) if *last == id(entry) => *count += 1,
+                _ => counts.push((id(entry), 1)),
+            }
+        }
+
+        match self.duplicates {
+            Duplicates::KeepFirst => entries.dedup_by_key(|entry| id(entry)),
+            Duplicates::KeepLast => {
+                entries.reverse();
+                entries.dedup_by_key(|entry| id(entry));
+                entries.reverse();
+            }
+            Duplicates::KeepAll => {}
+        }
+
+        self.found
+            .extend(
+                counts
+                    .into_iter()
+                    .filter(|(_, count)| *count > 1)
+                    .map(|(entry, count)| Duplicate {
+                        path: format!("{}[id={}]", path, entry),
+                        count,
+                        removed: match self.duplicates {
+                            Duplicates::KeepAll => 0,
+                            _ => count - 1,
+                        },
+                    }),
+            );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::{de::from_str, se::to_string};
+
+    use crate::{id::PageId, project::test::ORIGINAL_CONTENT, ProjectXML};
+
+    use super::{canonicalize, Duplicate, Duplicates};
+
+    fn deskew_page(id: u32, angle: &str) -> String {
+        format!(
+            r#"<page id="{}"><params angle="{}" mode="auto"><dependencies><rotation degrees="90"/><page-outline><point x="1143" y="0"/><point x="4960" y="0"/><point x="4960" y="7016"/><point x="1143" y="7016"/><point x="1143" y="0"/></page-outline></dependencies></params></page>"#,
+            id, angle
+        )
+    }
+
+    fn shuffled() -> String {
+        let start = ORIGINAL_CONTENT.find("<deskew>").unwrap() + "<deskew>".len();
+        let end = ORIGINAL_CONTENT.find("<image-settings><page id=\"4\"><image-params blackOnWhite=\"1\" bwThreshold=\"190\"/>").unwrap();
+        let (page_4, page_7) = ORIGINAL_CONTENT[start..end].split_at(
+            ORIGINAL_CONTENT[start..end]
+                .find(r#"<page id="7">"#)
+                .unwrap(),
+        );
+        let page_split = ORIGINAL_CONTENT
+            .find(r#"<image id="3" layoutType"#)
+            .unwrap();
+        let page_split_6 = ORIGINAL_CONTENT
+            .find(r#"<image id="6" layoutType"#)
+            .unwrap();
+        let page_split_end = ORIGINAL_CONTENT.find("</page-split>").unwrap();
+        format!(
+            "{}{}{}{}{}{}{}",
+            &ORIGINAL_CONTENT[..page_split],
+            &ORIGINAL_CONTENT[page_split_6..page_split_end],
+            &ORIGINAL_CONTENT[page_split..page_split_6],
+            &ORIGINAL_CONTENT[page_split_end..start],
+            page_7,
+            page_4,
+            &ORIGINAL_CONTENT[end..]
+        )
+    }
+
+    #[test]
+    fn it_sorts_entries_in_page_order() {
+        let content = shuffled();
+        assert_ne!(content, ORIGINAL_CONTENT);
+
+        let mut project: ProjectXML = from_str(&content).unwrap();
+        assert!(canonicalize(&mut project, Duplicates::default()).is_empty());
+        assert_eq!(to_string(&project).unwrap(), ORIGINAL_CONTENT);
+    }
+
+    #[test]
+    fn it_handles_duplicates() {
+        let content =
+            ORIGINAL_CONTENT.replacen("<deskew>", &format!("<deskew>{}", deskew_page(7, "1.5")), 1);
+        let angles = |duplicates| {
+            let mut project: ProjectXML = from_str(&content).unwrap();
+            let found = canonicalize(&mut project, duplicates);
+            let angles = project
+                .filters
+                .deskew
+                .page
+                .unwrap()
+                .iter()
+                .map(|page| (page.id, page.params.angle))
+                .collect::<Vec<_>>();
+            (found, angles)
+        };
+
+        let duplicate = |removed| Duplicate {
+            path: "project/filters/deskew/page[id=7]".to_owned(),
+            count: 2,
+            removed,
+        };
+        assert_eq!(
+            angles(Duplicates::KeepFirst),
+            (
+                vec![duplicate(1)],
+                vec![(PageId(4), 0.125), (PageId(7), 1.5)]
+            )
+        );
+        assert_eq!(angles(Duplicates::default()), angles(Duplicates::KeepLast));
+        assert_eq!(
+            angles(Duplicates::KeepLast),
+            (
+                vec![duplicate(1)],
+                vec![(PageId(4), 0.125), (PageId(7), 0.125)]
+            )
+        );
+        assert_eq!(
+            angles(Duplicates::KeepAll),
+            (
+                vec![duplicate(0)],
+                vec![(PageId(4), 0.125), (PageId(7), 1.5), (PageId(7), 0.125)]
+            )
+        );
+        assert_eq!(
+            duplicate(1).to_string(),
+            "project/filters/deskew/page[id=7]: 2 entries, 1 removed"
+        );
+    }
+}
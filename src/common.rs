@@ -1,36 +1,94 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DeserializeAs, SerializeAs};
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
+use super::{
+    number::QtNumber,
+    units::{Dpi, Mm, Px},
+};
+
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, PartialEq, Debug)]
+#[serde(
+    deny_unknown_fields,
+    bound(
+        serialize = "QtNumber: SerializeAs<T>",
+        deserialize = "QtNumber: DeserializeAs<'de, T>"
+    )
+)]
 pub struct Size<T> {
-    #[serde(rename(serialize = "@height"))]
+    #[serde(rename = "@height")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "T")]
     pub height: T,
 
-    #[serde(rename(serialize = "@width"))]
+    #[serde(rename = "@width")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "T")]
     pub width: T,
 }
 
 pub type IntSize = Size<u32>;
 pub type FloatSize = Size<f64>;
 
-#[derive(Serialize, Deserialize, Debug)]
+impl IntSize {
+    /// The size on paper of an image of this size scanned at `dpi`.
+    pub fn to_mm(&self, dpi: &DPI) -> Size<Mm> {
+        Size {
+            height: Px(self.height.into()).to_mm(dpi.vertical),
+            width: Px(self.width.into()).to_mm(dpi.horizontal),
+        }
+    }
+}
+
+impl Size<Px> {
+    /// The size on paper of pixels scanned at `dpi`.
+    pub fn to_mm(&self, dpi: &DPI) -> Size<Mm> {
+        Size {
+            height: self.height.to_mm(dpi.vertical),
+            width: self.width.to_mm(dpi.horizontal),
+        }
+    }
+}
+
+impl Size<Mm> {
+    /// The pixels this size covers when scanned at `dpi`.
+    pub fn to_px(&self, dpi: &DPI) -> Size<Px> {
+        Size {
+            height: self.height.to_px(dpi.vertical),
+            width: self.width.to_px(dpi.horizontal),
+        }
+    }
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct DPI {
-    #[serde(rename(serialize = "@horizontal"))]
-    pub horizontal: f64,
+    #[serde(rename = "@horizontal")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub horizontal: Dpi,
 
-    #[serde(rename(serialize = "@vertical"))]
-    pub vertical: f64,
+    #[serde(rename = "@vertical")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub vertical: Dpi,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Default, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Point {
-    #[serde(rename(serialize = "@x"))]
-    pub x: f64,
+    #[serde(rename = "@x")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub x: Px,
 
-    #[serde(rename(serialize = "@y"))]
-    pub y: f64,
+    #[serde(rename = "@y")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub y: Px,
 }
 
 impl PartialEq for Point {
@@ -40,3 +98,22 @@ impl PartialEq for Point {
 }
 
 impl Eq for Point {}
+
+impl Point {
+    pub fn new(x: Px, y: Px) -> Self {
+        Point { x, y }
+    }
+
+    pub fn translate(&self, dx: Px, dy: Px) -> Self {
+        Point::new(self.x + dx, self.y + dy)
+    }
+
+    /// Scales the point away from the origin.
+    pub fn scale(&self, sx: f64, sy: f64) -> Self {
+        Point::new(self.x * sx, self.y * sy)
+    }
+
+    pub fn distance(&self, other: &Point) -> Px {
+        Px((other.x - self.x).0.hypot((other.y - self.y).0))
+    }
+}
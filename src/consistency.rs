@@ -0,0 +1,308 @@
+use crate::{
+    common::{IntSize, Size},
+    filters::common::{rotation::Degrees, Outline, Rect},
+    id::PageId,
+    images::Image,
+    report::{Problem, Report, Severity},
+    units::{Mm, Px},
+};
+
+use super::ProjectXML;
+
+/// How far apart values computed from one another may be, as ScanTailor rounds
+/// some of what it stores and not the rest.
+#[derive(Clone, Copy, Debug)]
+pub struct Tolerances {
+    /// for positions and lengths in pixels
+    pub px: Px,
+    /// for lengths on paper
+    pub mm: Mm,
+}
+
+impl Default for Tolerances {
+    fn default() -> Self {
+        Tolerances {
+            px: Px(1f64),
+            mm: Mm(0.01),
+        }
+    }
+}
+
+/// Checks that what each filter stores agrees with what the filters before it store,
+/// which ScanTailor takes for granted when loading a project.
+pub fn check(project: &ProjectXML, tolerances: &Tolerances) -> Report {
+    let mut problems = vec![];
+    let mut report = |code: &'static str, page, image, path: String, message: String| {
+        problems.push(Problem {
+            code: code.into(),
+            severity: Severity::Error,
+            page,
+            image,
+            path,
+            message,
+        })
+    };
+    let filters = &project.filters;
+    let image_of = |page_id: PageId| {
+        let page = project.pages.page.iter().find(|page| page.id == page_id)?;
+        project
+            .images
+            .image
+            .iter()
+            .find(|image| image.id == page.image_id)
+    };
+
+    for split in filters.page_split.image.iter().flatten() {
+        let Some(image) = project
+            .images
+            .image
+            .iter()
+            .find(|image| image.id == split.id)
+        else {
+            continue;
+        };
+        let size = &split.params.dependencies.size;
+        if *size != image.size {
+            report(
+                "split_size_mismatch",
+                None,
+                Some(image.id),
+                format!(
+                    "project/filters/page-split/image[id={}]/params/dependencies/size",
+                    split.id
+                ),
+                format!(
+                    "split a {} image where the image is {}",
+                    size_px(size),
+                    size_px(&image.size)
+                ),
+            );
+        }
+    }
+
+    for deskew in filters.deskew.page.iter().flatten() {
+        let (Some(image), Outline::Filled(outline)) = (
+            image_of(deskew.id),
+            &deskew.params.dependencies.page_outline,
+        ) else {
+            continue;
+        };
+        let size = match deskew.params.dependencies.rotation.degrees {
+            Degrees::_0 | Degrees::_180 => image.size,
+            Degrees::_90 | Degrees::_270 => IntSize {
+                height: image.size.width,
+                width: image.size.height,
+            },
+        };
+        let bounds = grow(
+            &Rect::new(
+                Px(0f64),
+                Px(0f64),
+                Px(size.width.into()),
+                Px(size.height.into()),
+            ),
+            tolerances.px,
+        );
+        if !outline
+            .point
+            .iter()
+            .all(|point| bounds.contains_point(point))
+        {
+            report(
+                "outline_outside_image",
+                Some(deskew.id),
+                None,
+                format!(
+                    "project/filters/deskew/page[id={}]/params/dependencies/page-outline",
+                    deskew.id
+                ),
+                format!("outline goes beyond the {} oriented image", size_px(&size)),
+            );
+        }
+    }
+
+    for select in filters.select_content.page.iter().flatten() {
+        let params = &select.params;
+        let path = format!(
+            "project/filters/select-content/page[id={}]/params",
+            select.id
+        );
+        let content = Rect::from(&params.content_rect);
+        // ScanTailor stores an empty content rect and a size of -1 when there is no content
+        if content.is_empty() {
+            continue;
+        }
+        if !grow(&params.page_rect, tolerances.px).contains(&content) {
+            report(
+                "content_outside_page",
+                Some(select.id),
+                None,
+                format!("{}/content-rect", path),
+                "content rect goes beyond the page rect".to_owned(),
+            );
+        }
+        let Some(image) = image_of(select.id) else {
+            continue;
+        };
+        let expected = content_size_mm(&content, image);
+        if !same_size_mm(&params.content_size_mm, &expected, tolerances) {
+            report(
+                "content_size_mismatch",
+                Some(select.id),
+                None,
+                format!("{}/content-size-mm", path),
+                format!(
+                    "content is {} where its rect is {} at the image DPI",
+                    size_mm(&params.content_size_mm),
+                    size_mm(&expected)
+                ),
+            );
+        }
+    }
+
+    for layout in filters.page_layout.page.iter().flatten() {
+        let Some(select) = filters
+            .select_content
+            .page
+            .iter()
+            .flatten()
+            .find(|select| select.id == layout.id)
+        else {
+            continue;
+        };
+        let path = format!("project/filters/page-layout/page[id={}]/params", layout.id);
+        let (content, selected) = (
+            Rect::from(&layout.params.content_rect),
+            Rect::from(&select.params.content_rect),
+        );
+        if !same_rect(&content, &selected, tolerances) {
+            report(
+                "layout_content_mismatch",
+                Some(layout.id),
+                None,
+                format!("{}/contentRect", path),
+                "content rect differs from the one selected".to_owned(),
+            );
+        }
+        if !same_size_mm(
+            &layout.params.content_size,
+            &select.params.content_size_mm,
+            tolerances,
+        ) {
+            report(
+                "layout_content_mismatch",
+                Some(layout.id),
+                None,
+                format!("{}/contentSizeMM", path),
+                format!(
+                    "content is {} where {} was selected",
+                    size_mm(&layout.params.content_size),
+                    size_mm(&select.params.content_size_mm)
+                ),
+            );
+        }
+    }
+
+    Report { problems }
+}
+
+/// The size on paper of content found on the image, which ScanTailor scales to the lower
+/// of its DPIs beforehand.
+fn content_size_mm(content: &Rect, image: &Image) -> Size<Mm> {
+    let dpi = image.dpi.horizontal.min(image.dpi.vertical);
+    Size {
+        height: content.height.to_mm(dpi),
+        width: content.width.to_mm(dpi),
+    }
+}
+
+fn grow(rect: &Rect, by: Px) -> Rect {
+    Rect::new(
+        rect.x - by,
+        rect.y - by,
+        rect.width + by * 2f64,
+        rect.height + by * 2f64,
+    )
+}
+
+fn same_rect(a: &Rect, b: &Rect, tolerances: &Tolerances) -> bool {
+    [
+        (a.x, b.x),
+        (a.y, b.y),
+        (a.width, b.width),
+        (a.height, b.height),
+    ]
+    .into_iter()
+    .all(|(a, b)| (a - b).0.abs() <= tolerances.px.0)
+}
+
+fn same_size_mm(a: &Size<Mm>, b: &Size<Mm>, tolerances: &Tolerances) -> bool {
+    (a.width - b.width).0.abs() <= tolerances.mm.0
+        && (a.height - b.height).0.abs() <= tolerances.mm.0
+}
+
+fn size_px(size: &IntSize) -> String {
+    format!("{}x{} px", size.width, size.height)
+}
+
+fn size_mm(size: &Size<Mm>) -> String {
+    format!("{:.2}x{:.2} mm", size.width.0, size.height.0)
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::de::from_str;
+
+    use crate::{project::test::ORIGINAL_CONTENT, units::Mm, ProjectXML};
+
+    use super::{check, Tolerances};
+
+    #[test]
+    fn it_finds_nothing_in_a_consistent_project() {
+        let project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        assert!(check(&project, &Tolerances::default()).is_empty());
+    }
+
+    #[test]
+    fn it_finds_stages_that_disagree() {
+        let content = ORIGINAL_CONTENT
+            .replacen(
+                r#"<size height="4961" width="7016"/><layoutType>"#,
+                r#"<size height="4961" width="7000"/><layoutType>"#,
+                1,
+            )
+            .replacen(
+                r#"<point x="4960" y="7016"/>"#,
+                r#"<point x="4960" y="7100"/>"#,
+                1,
+            )
+            .replacen(
+                r#"<content-rect height="2236" width="3328" x="116" y="364"/>"#,
+                r#"<content-rect height="2236" width="3328" x="616" y="364"/>"#,
+                1,
+            )
+            .replacen(
+                r#"<content-size-mm height="94.82685632037932""#,
+                r#"<content-size-mm height="94.8""#,
+                1,
+            );
+        let project: ProjectXML = from_str(&content).unwrap();
+
+        let report = check(&project, &Tolerances::default());
+        assert_eq!(
+            report.to_string(),
+            "error[split_size_mismatch]: project/filters/page-split/image[id=3]/params/dependencies/size: split a 7000x4961 px image where the image is 7016x4961 px\n\
+             error[outline_outside_image]: project/filters/deskew/page[id=4]/params/dependencies/page-outline: outline goes beyond the 4961x7016 px oriented image\n\
+             error[content_outside_page]: project/filters/select-content/page[id=4]/params/content-rect: content rect goes beyond the page rect\n\
+             error[content_size_mismatch]: project/filters/select-content/page[id=7]/params/content-size-mm: content is 140.89x94.80 mm where its rect is 140.89x94.83 mm at the image DPI\n\
+             error[layout_content_mismatch]: project/filters/page-layout/page[id=4]/params/contentRect: content rect differs from the one selected\n\
+             error[layout_content_mismatch]: project/filters/page-layout/page[id=7]/params/contentSizeMM: content is 140.89x94.83 mm where 140.89x94.80 mm was selected\n"
+        );
+
+        let tolerances = Tolerances {
+            mm: Mm(0.05),
+            ..Tolerances::default()
+        };
+        assert_eq!(check(&project, &tolerances).problems.len(), 4);
+    }
+}
@@ -1,20 +1,23 @@
 use std::path::PathBuf;
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+use super::id::DirectoryId;
+
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Directories {
     pub directory: Vec<Directory>,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Directory {
-    #[serde(rename(serialize = "@id"))]
-    pub id: i32,
+    #[serde(rename = "@id")]
+    pub id: DirectoryId,
 
-    #[serde(rename(serialize = "@path"))]
+    #[serde(rename = "@path")]
     pub path: PathBuf,
 }
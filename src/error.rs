@@ -0,0 +1,255 @@
+use std::{
+    cell::Cell,
+    fmt::{self, Display},
+    io::{self, BufRead, Read},
+    iter,
+    rc::Rc,
+};
+
+use quick_xml::{events::Event, DeError, Reader};
+use serde::de::DeserializeOwned;
+
+/// A project file that could not be read, with where it went wrong.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ParseError {
+    pub message: String,
+    /// byte offset of the start tag of the element being read
+    pub offset: usize,
+    /// 1-based
+    pub line: usize,
+    /// 1-based, in characters
+    pub column: usize,
+    /// path of the element being read, as in `project/filters/page-split/image[id=14]/params/pages`
+    pub path: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}: {}",
+            self.line, self.column, self.path, self.message
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    pub(crate) fn new(content: &str, offset: usize, path: String, message: String) -> Self {
+        let bytes = &content.as_bytes()[..offset.min(content.len())];
+        let line_start = bytes
+            .iter()
+            .rposition(|byte| *byte == b'\n')
+            .map_or(0, |newline| newline + 1);
+        ParseError {
+            message,
+            offset,
+            line: bytes.iter().filter(|byte| **byte == b'\n').count() + 1,
+            // counting every byte that does not continue a UTF-8 sequence
+            column: bytes[line_start..]
+                .iter()
+                .filter(|byte| **byte & 0xC0 != 0x80)
+                .count()
+                + 1,
+            path,
+        }
+    }
+}
+
+/// Deserializes a project file, or a part of one, locating any error.
+pub(crate) fn from_str<T: DeserializeOwned>(content: &str) -> Result<T, ParseError> {
+    from_rewritten(content, content)
+}
+
+/// Deserializes `content` rewritten from `original`, as when unknown elements were removed,
+/// locating any error in `original`. Elements of `content` are looked up in `original` by
+/// path, so those only found in `content`, as when added by a migration, are located at
+/// the start of `original`.
+pub(crate) fn from_rewritten<T: DeserializeOwned>(
+    content: &str,
+    original: &str,
+) -> Result<T, ParseError> {
+    let error = match quick_xml::de::from_str(content) {
+        Ok(value) => return Ok(value),
+        Err(error) => error,
+    };
+
+    // quick-xml does not tell where it stopped, so read again counting what it consumed
+    let consumed = Rc::new(Cell::new(0));
+    let _ = quick_xml::de::from_reader::<_, T>(Counting {
+        content: content.as_bytes(),
+        consumed: consumed.clone(),
+    });
+
+    // the deserializer always reads one event ahead, unless reading it failed
+    let mut read = tags(content)
+        .take_while(|tag| tag.end <= consumed.get())
+        .collect::<Vec<_>>();
+    if !matches!(error, DeError::InvalidXml(_)) {
+        read.pop();
+    }
+    let Some(tag) = read.pop() else {
+        return Err(ParseError::new(
+            original,
+            0,
+            String::new(),
+            error.to_string(),
+        ));
+    };
+    let ordinal = tags(content)
+        .filter(|other| other.opening && other.path == tag.path && other.start < tag.start)
+        .count();
+    let offset = tags(original)
+        .filter(|other| other.opening && other.path == tag.path)
+        .nth(ordinal)
+        .map_or(0, |tag| tag.start);
+    Err(ParseError::new(
+        original,
+        offset,
+        tag.path,
+        error.to_string(),
+    ))
+}
+
+struct Counting<'a> {
+    content: &'a [u8],
+    consumed: Rc<Cell<usize>>,
+}
+
+impl Read for Counting<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.content.read(buf)?;
+        self.consumed.set(self.consumed.get() + read);
+        Ok(read)
+    }
+}
+
+impl BufRead for Counting<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.content)
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.content.consume(amount);
+        self.consumed.set(self.consumed.get() + amount);
+    }
+}
+
+/// An event of a document as the deserializer reads it.
+struct Tag {
+    /// path of the element the event is part of
+    path: String,
+    /// where the start tag of that element starts
+    start: usize,
+    /// where the event ends
+    end: usize,
+    /// whether this is the start tag
+    opening: bool,
+}
+
+/// The events of a document in the order the deserializer reads them, with empty elements
+/// expanded and whitespace skipped, up to the first syntax error.
+fn tags(content: &str) -> impl Iterator<Item = Tag> + '_ {
+    let mut reader = Reader::from_str(content);
+    reader.expand_empty_elements(true);
+    let mut open: Vec<(String, usize)> = vec![];
+    iter::from_fn(move || loop {
+        let start = reader.buffer_position();
+        let event = reader.read_event().ok()?;
+        let end = reader.buffer_position();
+        let closing = matches!(event, Event::End(_));
+        let opening = match event {
+            Event::Start(tag) => {
+                let name = String::from_utf8_lossy(tag.name().as_ref()).into_owned();
+                let segment = match tag.try_get_attribute("id").ok().flatten() {
+                    Some(id) => format!("{}[id={}]", name, String::from_utf8_lossy(&id.value)),
+                    None => name,
+                };
+                open.push((segment, start));
+                true
+            }
+            Event::End(_) | Event::CData(_) => false,
+            Event::Text(text) if !text.iter().all(u8::is_ascii_whitespace) => false,
+            Event::Eof => return None,
+            _ => continue,
+        };
+        let tag = Tag {
+            path: open
+                .iter()
+                .map(|(segment, _)| segment.as_str())
+                .collect::<Vec<_>>()
+                .join("/"),
+            start: open.last().map_or(0, |(_, start)| *start),
+            end,
+            opening,
+        };
+        if closing {
+            open.pop();
+        }
+        return Some(tag);
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::project::test::ORIGINAL_CONTENT;
+    use crate::ProjectXML;
+
+    use super::ParseError;
+
+    #[test]
+    fn it_locates_missing_fields() {
+        let content = ORIGINAL_CONTENT.replacen(
+            r#"<size height="4961" width="7016"/><dpi horizontal="600" vertical="600"/></image><image fileId="5""#,
+            r#"<size height="4961" width="7016"/></image><image fileId="5""#,
+            1,
+        );
+        let error = content.parse::<ProjectXML>().unwrap_err();
+        assert_eq!(error.message, "missing field `dpi`");
+        assert_eq!(error.path, "project/images/image[id=3]");
+        assert!(content[error.offset..].starts_with(r#"<image fileId="2""#));
+        assert_eq!((error.line, error.column), (1, error.offset + 1));
+    }
+
+    #[test]
+    fn it_locates_errors_on_later_lines() {
+        let content = ORIGINAL_CONTENT
+            .replace("><", ">\n<")
+            .replacen(
+                r#"<pages type="single-cut">"#,
+                r#"<pages type="two-pages">"#,
+                2,
+            )
+            .replacen(
+                r#"<pages type="two-pages">"#,
+                r#"<pages type="single-cut">"#,
+                1,
+            );
+        let error = content.parse::<ProjectXML>().unwrap_err();
+        assert_eq!(error.message, "unknown pages structure");
+        assert_eq!(
+            error.path,
+            "project/filters/page-split/image[id=6]/params/pages"
+        );
+        let line = content.lines().nth(error.line - 1).unwrap();
+        assert_eq!(line, r#"<pages type="two-pages">"#);
+        assert_eq!(error.column, 1);
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "{}:1: project/filters/page-split/image[id=6]/params/pages: unknown pages structure",
+                error.line
+            )
+        );
+    }
+
+    #[test]
+    fn it_locates_syntax_errors() {
+        let content = ORIGINAL_CONTENT.replacen(r#"<rotation degrees="90"/>"#, "<rotation", 1);
+        let error: ParseError = content.parse::<ProjectXML>().unwrap_err();
+        assert!(error
+            .path
+            .starts_with("project/filters/fix-orientation/image[id=3]"));
+    }
+}
@@ -1,17 +1,20 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+use super::id::FileId;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct FileNameDisambiguation {
     pub mapping: Vec<Mapping>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Mapping {
-    #[serde(rename(serialize = "@file"))]
-    pub file: u32,
+    #[serde(rename = "@file")]
+    pub file: FileId,
 
-    #[serde(rename(serialize = "@label"))]
+    #[serde(rename = "@label")]
     pub label: String,
 }
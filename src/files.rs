@@ -1,20 +1,23 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+use super::id::{DirectoryId, FileId};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Files {
     pub file: Vec<File>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct File {
-    #[serde(rename(serialize = "@dirId", deserialize = "dirId"))]
-    pub dir_id: u32,
+    #[serde(rename = "@dirId")]
+    pub dir_id: DirectoryId,
 
-    #[serde(rename(serialize = "@id"))]
-    pub id: u32,
+    #[serde(rename = "@id")]
+    pub id: FileId,
 
-    #[serde(rename(serialize = "@name"))]
+    #[serde(rename = "@name")]
     pub name: String,
 }
@@ -1,31 +1,35 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, BoolFromInt};
 
-#[derive(Serialize, Deserialize, Debug)]
+use crate::id::PageId;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct ImageSettings {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub page: Option<Vec<ImageSettingsPage>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct ImageSettingsPage {
-    #[serde(rename(serialize = "@id"))]
-    pub id: u32,
+    #[serde(rename = "@id")]
+    pub id: PageId,
 
     #[serde(rename = "image-params")]
     pub image_params: ImageParams,
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct ImageParams {
-    #[serde(rename(serialize = "@blackOnWhite", deserialize = "blackOnWhite"))]
+    #[serde(rename = "@blackOnWhite")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub black_on_white: bool,
 
-    #[serde(rename(serialize = "@bwThreshold", deserialize = "bwThreshold"))]
+    #[serde(rename = "@bwThreshold")]
     pub bw_threshold: u8,
 }
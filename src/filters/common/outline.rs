@@ -1,46 +1,113 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use validator::{Validate, ValidationError};
 
-use super::super::super::common::Point;
+use crate::{units::Px, validation::invalid};
 
-#[derive(Serialize, Debug)]
-#[serde(untagged)]
+use super::{super::super::common::Point, Rect};
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
+#[serde(untagged, from = "Points")]
 pub enum Outline {
     Filled(FilledOutline),
     Empty(EmptyOutline),
 }
 
-// deriving Deserialize caused failure to match any variant
-impl<'de> Deserialize<'de> for Outline {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        #[derive(Deserialize)]
-        #[serde(deny_unknown_fields)]
-        pub struct S {
-            pub point: Option<Vec<Point>>,
-        }
+// untagged variants are buffered before they are matched, which loses
+// repeated elements, so both are read as one optional list instead
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Points {
+    point: Option<Vec<Point>>,
+}
 
-        let s = S::deserialize(deserializer)?;
-        match s.point {
-            Some(point) => Ok(Outline::Filled(FilledOutline { point })),
-            None => Ok(Outline::Empty(EmptyOutline {})),
+impl From<Points> for Outline {
+    fn from(points: Points) -> Self {
+        match points.point {
+            Some(point) => Outline::Filled(FilledOutline { point }),
+            None => Outline::Empty(EmptyOutline {}),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Clone, PartialEq, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct FilledOutline {
     #[validate(length(min = 5, max = 6), custom = "validate_outline")]
     pub point: Vec<Point>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct EmptyOutline {}
 
+impl FilledOutline {
+    /// Closes the polygon the way ScanTailor writes outlines, with the first point
+    /// repeated at the end.
+    pub fn from_polygon(mut polygon: Vec<Point>) -> Self {
+        if let Some(first) = polygon.first().copied() {
+            if polygon.last() != Some(&first) || polygon.len() == 1 {
+                polygon.push(first);
+            }
+        }
+        FilledOutline { point: polygon }
+    }
+
+    /// The corners of the outline, without repeating the first one.
+    pub fn polygon(&self) -> &[Point] {
+        match self.point.split_last() {
+            Some((last, rest)) if rest.first() == Some(last) => rest,
+            _ => &self.point,
+        }
+    }
+
+    /// Area in square pixels, whichever way the outline turns.
+    pub fn area(&self) -> f64 {
+        let polygon = self.polygon();
+        let twice = polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .map(|(a, b)| a.x.0 * b.y.0 - b.x.0 * a.y.0)
+            .sum::<f64>();
+        (twice / 2f64).abs()
+    }
+
+    /// Whether the point is inside the outline, by the even-odd rule.
+    pub fn contains(&self, point: &Point) -> bool {
+        let polygon = self.polygon();
+        let mut inside = false;
+        for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+            if (a.y > point.y) != (b.y > point.y)
+                && point.x < a.x + (b.x - a.x) * ((point.y - a.y) / (b.y - a.y))
+            {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    pub fn bounding_box(&self) -> Option<Rect> {
+        Rect::bounding(&self.point)
+    }
+
+    pub fn translate(&self, dx: Px, dy: Px) -> Self {
+        FilledOutline {
+            point: self
+                .point
+                .iter()
+                .map(|point| point.translate(dx, dy))
+                .collect(),
+        }
+    }
+
+    /// Scales the outline away from the origin.
+    pub fn scale(&self, sx: f64, sy: f64) -> Self {
+        FilledOutline {
+            point: self.point.iter().map(|point| point.scale(sx, sy)).collect(),
+        }
+    }
+}
+
 impl Validate for Outline {
     fn validate(&self) -> Result<(), validator::ValidationErrors> {
         match self {
@@ -50,8 +117,40 @@ impl Validate for Outline {
     }
 }
 
-fn validate_outline(points: &Vec<Point>) -> Result<(), ValidationError> {
+fn validate_outline(points: &[Point]) -> Result<(), ValidationError> {
     (points.first().unwrap() == points.last().unwrap())
-        .then(|| ())
-        .ok_or_else(|| ValidationError::new("invalid outline: first & last points do not match"))
+        .then_some(())
+        .ok_or_else(|| invalid("open_outline", "first & last points do not match"))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{common::Point, filters::common::Rect, units::Px};
+
+    use super::FilledOutline;
+
+    #[test]
+    fn it_computes_with_outlines() {
+        // a page rotated by a few degrees, as in a deskew page outline
+        let outline = FilledOutline::from_polygon(vec![
+            Point::new(Px(10f64), Px(0f64)),
+            Point::new(Px(110f64), Px(10f64)),
+            Point::new(Px(100f64), Px(210f64)),
+            Point::new(Px(0f64), Px(200f64)),
+        ]);
+        assert_eq!(outline.point.len(), 5);
+        assert_eq!(outline.polygon().len(), 4);
+        assert_eq!(outline.area(), 20100f64);
+        assert!(outline.contains(&Point::new(Px(55f64), Px(105f64))));
+        assert!(!outline.contains(&Point::new(Px(2f64), Px(2f64))));
+        assert_eq!(
+            outline.bounding_box(),
+            Some(Rect::new(Px(0f64), Px(0f64), Px(110f64), Px(210f64)))
+        );
+
+        let moved = outline.translate(Px(-10f64), Px(5f64)).scale(2f64, 2f64);
+        assert_eq!(moved.point[0], Point::new(Px(0f64), Px(10f64)));
+        assert_eq!(moved.area(), outline.area() * 4f64);
+        assert_eq!(FilledOutline::from_polygon(moved.point.clone()), moved);
+    }
 }
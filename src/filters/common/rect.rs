@@ -1,53 +1,231 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use validator::Validate;
 
+use crate::{
+    common::Point,
+    number::QtNumber,
+    units::{validate_non_negative, Px},
+};
+
+use super::FilledOutline;
+
 // TODO: should some rectangle coordinates be strictly positive?
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Clone, Copy, PartialEq, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Rect {
-    #[serde(rename(serialize = "@height"))]
-    #[validate(range(min = 0))]
-    pub height: f64,
+    #[serde(rename = "@height")]
+    #[validate(custom = "validate_non_negative")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64", range(min = 0))]
+    pub height: Px,
 
-    #[serde(rename(serialize = "@width"))]
-    #[validate(range(min = 0))]
-    pub width: f64,
+    #[serde(rename = "@width")]
+    #[validate(custom = "validate_non_negative")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64", range(min = 0))]
+    pub width: Px,
 
-    #[serde(rename(serialize = "@x"))]
-    pub x: f64,
+    #[serde(rename = "@x")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub x: Px,
 
-    #[serde(rename(serialize = "@y"))]
-    pub y: f64,
+    #[serde(rename = "@y")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub y: Px,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Clone, Copy, PartialEq, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct NonNegativeRegionRect {
-    #[serde(rename(serialize = "@height"))]
-    #[validate(range(min = 0))]
-    pub height: f64,
+    #[serde(rename = "@height")]
+    #[validate(custom = "validate_non_negative")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64", range(min = 0))]
+    pub height: Px,
+
+    #[serde(rename = "@width")]
+    #[validate(custom = "validate_non_negative")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64", range(min = 0))]
+    pub width: Px,
+
+    #[serde(rename = "@x")]
+    #[validate(custom = "validate_non_negative")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64", range(min = 0))]
+    pub x: Px,
+
+    #[serde(rename = "@y")]
+    #[validate(custom = "validate_non_negative")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64", range(min = 0))]
+    pub y: Px,
+}
+
+// edges are part of a rectangle, as in ScanTailor, where a content rect touching the
+// page rect is still inside of it
+
+impl Rect {
+    pub fn new(x: Px, y: Px, width: Px, height: Px) -> Self {
+        Rect {
+            height,
+            width,
+            x,
+            y,
+        }
+    }
+
+    /// The smallest rectangle containing every point, if there are any.
+    pub fn bounding(points: &[Point]) -> Option<Self> {
+        let first = points.first()?;
+        let (mut left, mut top, mut right, mut bottom) = (first.x, first.y, first.x, first.y);
+        for point in points.iter().skip(1) {
+            left = left.min(point.x);
+            top = top.min(point.y);
+            right = right.max(point.x);
+            bottom = bottom.max(point.y);
+        }
+        Some(Rect::new(left, top, right - left, bottom - top))
+    }
+
+    pub fn left(&self) -> Px {
+        self.x
+    }
+
+    pub fn top(&self) -> Px {
+        self.y
+    }
+
+    pub fn right(&self) -> Px {
+        self.x + self.width
+    }
+
+    pub fn bottom(&self) -> Px {
+        self.y + self.height
+    }
+
+    /// Area in square pixels.
+    pub fn area(&self) -> f64 {
+        self.width.0 * self.height.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width.0 <= 0f64 || self.height.0 <= 0f64
+    }
+
+    pub fn contains_point(&self, point: &Point) -> bool {
+        (self.left()..=self.right()).contains(&point.x)
+            && (self.top()..=self.bottom()).contains(&point.y)
+    }
+
+    pub fn contains(&self, other: &Rect) -> bool {
+        self.left() <= other.left()
+            && self.top() <= other.top()
+            && other.right() <= self.right()
+            && other.bottom() <= self.bottom()
+    }
+
+    /// The area both rectangles cover, if they overlap by more than an edge.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let left = self.left().max(other.left());
+        let top = self.top().max(other.top());
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        (left < right && top < bottom).then(|| Rect::new(left, top, right - left, bottom - top))
+    }
 
-    #[serde(rename(serialize = "@width"))]
-    #[validate(range(min = 0))]
-    pub width: f64,
+    pub fn translate(&self, dx: Px, dy: Px) -> Self {
+        Rect::new(self.x + dx, self.y + dy, self.width, self.height)
+    }
+
+    /// Scales the rectangle away from the origin.
+    pub fn scale(&self, sx: f64, sy: f64) -> Self {
+        Rect::new(self.x * sx, self.y * sy, self.width * sx, self.height * sy)
+    }
+
+    /// The corners clockwise from the top left one, y growing downwards.
+    pub fn corners(&self) -> [Point; 4] {
+        let (left, top, right, bottom) = (self.left(), self.top(), self.right(), self.bottom());
+        [
+            Point::new(left, top),
+            Point::new(right, top),
+            Point::new(right, bottom),
+            Point::new(left, bottom),
+        ]
+    }
+
+    /// The corners as a closed polygon, as ScanTailor writes outlines.
+    pub fn to_polygon(&self) -> Vec<Point> {
+        let corners = self.corners();
+        let mut polygon = corners.to_vec();
+        polygon.push(corners[0]);
+        polygon
+    }
+}
 
-    #[serde(rename(serialize = "@x"))]
-    #[validate(range(min = 0))]
-    pub x: f64,
+impl From<&NonNegativeRegionRect> for Rect {
+    fn from(rect: &NonNegativeRegionRect) -> Self {
+        Rect::new(rect.x, rect.y, rect.width, rect.height)
+    }
+}
 
-    #[serde(rename(serialize = "@y"))]
-    #[validate(range(min = 0))]
-    pub y: f64,
+impl TryFrom<&Rect> for NonNegativeRegionRect {
+    type Error = &'static str;
+
+    fn try_from(rect: &Rect) -> Result<Self, Self::Error> {
+        if rect.x.0 < 0f64 || rect.y.0 < 0f64 {
+            return Err("rect starts at negative coordinates");
+        }
+        Ok(NonNegativeRegionRect {
+            height: rect.height,
+            width: rect.width,
+            x: rect.x,
+            y: rect.y,
+        })
+    }
+}
+
+impl From<&Rect> for FilledOutline {
+    fn from(rect: &Rect) -> Self {
+        FilledOutline {
+            point: rect.to_polygon(),
+        }
+    }
+}
+
+impl TryFrom<&FilledOutline> for Rect {
+    type Error = &'static str;
+
+    /// Succeeds if the outline is an upright rectangle.
+    fn try_from(outline: &FilledOutline) -> Result<Self, Self::Error> {
+        let polygon = outline.polygon();
+        let rect = Rect::bounding(polygon).ok_or("outline has no points")?;
+        let corners = rect.corners();
+        (polygon.len() == 4
+            && polygon.iter().all(|point| corners.contains(point))
+            && outline.area() == rect.area())
+        .then_some(rect)
+        .ok_or("outline is not an upright rectangle")
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
     use validator::Validate;
 
     use crate::{
-        filters::common::{NonNegativeRegionRect, Rect},
+        common::Point,
+        filters::common::{FilledOutline, NonNegativeRegionRect, Rect},
+        project::test::ORIGINAL_CONTENT,
+        units::Px,
         ProjectXML,
     };
 
@@ -111,4 +289,48 @@ mod test {
             assert_eq!(rect.validate().is_ok(), validates);
         }
     }
+
+    #[test]
+    fn it_computes_with_rects() {
+        let rect = Rect::new(Px(10f64), Px(20f64), Px(100f64), Px(50f64));
+        assert_eq!((rect.right(), rect.bottom()), (Px(110f64), Px(70f64)));
+        assert_eq!(rect.area(), 5000f64);
+        assert!(rect.contains_point(&Point::new(Px(110f64), Px(20f64))));
+        assert!(!rect.contains_point(&Point::new(Px(9f64), Px(30f64))));
+
+        let other = Rect::new(Px(60f64), Px(0f64), Px(100f64), Px(40f64));
+        assert_eq!(
+            rect.intersection(&other),
+            Some(Rect::new(Px(60f64), Px(20f64), Px(50f64), Px(20f64)))
+        );
+        assert_eq!(
+            rect.intersection(&rect.translate(Px(100f64), Px(0f64))),
+            None
+        );
+        assert!(!rect.contains(&other));
+        assert!(rect.contains(&rect.intersection(&other).unwrap()));
+        assert_eq!(
+            rect.scale(2f64, 0.5),
+            Rect::new(Px(20f64), Px(10f64), Px(200f64), Px(25f64))
+        );
+
+        let outline = FilledOutline::from(&rect);
+        assert_eq!(outline.point.len(), 5);
+        assert_eq!(outline.bounding_box(), Some(rect));
+        assert_eq!(Rect::try_from(&outline), Ok(rect));
+        let mut skewed = outline.clone();
+        skewed.point[1].x += Px(1f64);
+        assert!(Rect::try_from(&skewed).is_err());
+
+        assert!(NonNegativeRegionRect::try_from(&rect.translate(Px(-20f64), Px(0f64))).is_err());
+    }
+
+    #[test]
+    fn it_finds_content_inside_the_page() {
+        let project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        for page in project.filters.select_content.page.unwrap() {
+            let content = Rect::from(&page.params.content_rect);
+            assert!(page.params.page_rect.contains(&content));
+        }
+    }
 }
@@ -1,13 +1,14 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Rotation {
-    #[serde(rename(serialize = "@degrees"))]
+    #[serde(rename = "@degrees")]
     pub degrees: Degrees,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 pub enum Degrees {
     #[serde(rename = "0")]
     _0,
@@ -21,7 +22,7 @@ pub enum Degrees {
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
 
     use crate::{filters::common::rotation::Degrees, ProjectXML};
 
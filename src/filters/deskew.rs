@@ -1,9 +1,13 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
 use validator::Validate;
 
+use crate::{id::PageId, number::QtNumber};
+
 use super::common::{ImageSettings, Outline, Rotation};
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Deskew {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -14,38 +18,41 @@ pub struct Deskew {
     pub image_settings: ImageSettings,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Page {
-    #[serde(rename(serialize = "@id"))]
-    pub id: u32,
+    #[serde(rename = "@id")]
+    pub id: PageId,
 
     #[validate]
     pub params: Params,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Params {
-    #[serde(rename(serialize = "@angle"))]
+    #[serde(rename = "@angle")]
     #[validate(range(min = -45, max = 45))]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub angle: f64,
 
-    #[serde(rename(serialize = "@mode"))]
+    #[serde(rename = "@mode")]
     pub mode: Mode,
 
     #[validate]
     pub dependencies: Dependencies,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub enum Mode {
     Auto,
     Manual,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Dependencies {
     pub rotation: Rotation,
@@ -57,7 +64,7 @@ pub struct Dependencies {
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
     use validator::Validate;
 
     use crate::{filters::deskew::Mode, ProjectXML};
@@ -1,8 +1,11 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::id::ImageId;
+
 use super::common::ImageSettings;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct FixOrientation {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -12,24 +15,24 @@ pub struct FixOrientation {
     pub image_settings: ImageSettings,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Image {
-    #[serde(rename(serialize = "@id"))]
-    pub id: u32,
+    #[serde(rename = "@id")]
+    pub id: ImageId,
 
     pub rotation: Rotation,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Rotation {
-    #[serde(rename(serialize = "@degrees"))]
+    #[serde(rename = "@degrees")]
     pub degrees: Degrees,
 }
 
 // clockwise
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 pub enum Degrees {
     #[serde(rename = "90")]
     _90,
@@ -41,7 +44,7 @@ pub enum Degrees {
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
 
     use crate::{filters::fix_orientation::Degrees, ProjectXML};
 
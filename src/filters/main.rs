@@ -1,12 +1,15 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::id::{ImageId, PageId};
+
 use super::{
     deskew::Deskew, fix_orientation::FixOrientation, output::Output, page_layout::PageLayout,
     page_split::PageSplit, select_content::SelectContent,
 };
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Filters {
     #[serde(rename = "fix-orientation")]
@@ -30,3 +33,56 @@ pub struct Filters {
     #[validate]
     pub output: Output,
 }
+
+/// Something done to the entries of each filter section, see [`Filters::visit_entries`].
+pub(crate) trait EntryVisitor {
+    fn images<T>(&mut self, entries: Option<&mut Vec<T>>, id: fn(&T) -> ImageId, path: &str);
+    fn pages<T>(&mut self, entries: Option<&mut Vec<T>>, id: fn(&T) -> PageId, path: &str);
+}
+
+impl Filters {
+    /// Hands the entries of every filter section to `visitor`, in file order, so that
+    /// passes over all of them cannot miss one.
+    pub(crate) fn visit_entries(&mut self, visitor: &mut impl EntryVisitor) {
+        visitor.images(
+            self.fix_orientation.image.as_mut(),
+            |image| image.id,
+            "project/filters/fix-orientation/image",
+        );
+        visitor.pages(
+            self.fix_orientation.image_settings.page.as_mut(),
+            |page| page.id,
+            "project/filters/fix-orientation/image-settings/page",
+        );
+        visitor.images(
+            self.page_split.image.as_mut(),
+            |image| image.id,
+            "project/filters/page-split/image",
+        );
+        visitor.pages(
+            self.deskew.page.as_mut(),
+            |page| page.id,
+            "project/filters/deskew/page",
+        );
+        visitor.pages(
+            self.deskew.image_settings.page.as_mut(),
+            |page| page.id,
+            "project/filters/deskew/image-settings/page",
+        );
+        visitor.pages(
+            self.select_content.page.as_mut(),
+            |page| page.id,
+            "project/filters/select-content/page",
+        );
+        visitor.pages(
+            self.page_layout.page.as_mut(),
+            |page| page.id,
+            "project/filters/page-layout/page",
+        );
+        visitor.pages(
+            Some(&mut self.output.page),
+            |page| page.id,
+            "project/filters/output/page",
+        );
+    }
+}
@@ -7,4 +7,5 @@ pub mod page_layout;
 pub mod page_split;
 pub mod select_content;
 
+pub(crate) use main::EntryVisitor;
 pub use main::Filters;
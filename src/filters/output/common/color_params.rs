@@ -1,23 +1,161 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, BoolFromInt};
-use validator::Validate;
+use validator::{Validate, ValidationErrors};
 
-// TODO: change to enum depending on color mode
-#[derive(Serialize, Deserialize, Validate, Debug)]
+use crate::number::QtNumber;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(tag = "@colorMode", try_from = "ColorParamsFields")]
+pub enum ColorParams {
+    #[serde(rename = "bw")]
+    BlackAndWhite(BlackAndWhiteParams),
+    #[serde(rename = "colorOrGray")]
+    ColorOrGrayscale(ColorOrGrayscaleParams),
+    #[serde(rename = "mixed")]
+    Mixed(MixedParams),
+}
+
+// as for the pages of page split, the settings are read first and matched with the
+// mode after, so that a mode missing its settings is reported as such
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColorParamsFields {
+    #[serde(rename = "@colorMode")]
+    color_mode: ColorMode,
+
+    #[serde(rename = "color-or-grayscale")]
+    color_or_grayscale: ColorOrGrayscale,
+
+    bw: Option<BW>,
+}
+
+impl TryFrom<ColorParamsFields> for ColorParams {
+    type Error = &'static str;
+
+    fn try_from(fields: ColorParamsFields) -> Result<Self, Self::Error> {
+        let ColorParamsFields {
+            color_mode,
+            color_or_grayscale,
+            bw,
+        } = fields;
+
+        match (color_mode, bw) {
+            (ColorMode::BlackAndWhite, Some(bw)) => {
+                Ok(ColorParams::BlackAndWhite(BlackAndWhiteParams {
+                    color_or_grayscale,
+                    bw,
+                }))
+            }
+            (ColorMode::ColorOrGrayscale, bw) => {
+                Ok(ColorParams::ColorOrGrayscale(ColorOrGrayscaleParams {
+                    color_or_grayscale,
+                    bw,
+                }))
+            }
+            (ColorMode::Mixed, Some(bw)) => Ok(ColorParams::Mixed(MixedParams {
+                color_or_grayscale,
+                bw,
+            })),
+            _ => Err("missing bw settings for the color mode"),
+        }
+    }
+}
+
+impl ColorParams {
+    pub fn color_mode(&self) -> ColorMode {
+        match self {
+            ColorParams::BlackAndWhite(_) => ColorMode::BlackAndWhite,
+            ColorParams::ColorOrGrayscale(_) => ColorMode::ColorOrGrayscale,
+            ColorParams::Mixed(_) => ColorMode::Mixed,
+        }
+    }
+
+    /// color or grayscale settings, if the color mode uses them
+    pub fn color_or_grayscale(&self) -> Option<&ColorOrGrayscale> {
+        match self {
+            ColorParams::BlackAndWhite(_) => None,
+            ColorParams::ColorOrGrayscale(params) => Some(&params.color_or_grayscale),
+            ColorParams::Mixed(params) => Some(&params.color_or_grayscale),
+        }
+    }
+
+    pub fn color_or_grayscale_mut(&mut self) -> Option<&mut ColorOrGrayscale> {
+        match self {
+            ColorParams::BlackAndWhite(_) => None,
+            ColorParams::ColorOrGrayscale(params) => Some(&mut params.color_or_grayscale),
+            ColorParams::Mixed(params) => Some(&mut params.color_or_grayscale),
+        }
+    }
+
+    /// black and white settings, if the color mode uses them
+    pub fn bw(&self) -> Option<&BW> {
+        match self {
+            ColorParams::BlackAndWhite(params) => Some(&params.bw),
+            ColorParams::ColorOrGrayscale(_) => None,
+            ColorParams::Mixed(params) => Some(&params.bw),
+        }
+    }
+
+    pub fn bw_mut(&mut self) -> Option<&mut BW> {
+        match self {
+            ColorParams::BlackAndWhite(params) => Some(&mut params.bw),
+            ColorParams::ColorOrGrayscale(_) => None,
+            ColorParams::Mixed(params) => Some(&mut params.bw),
+        }
+    }
+}
+
+impl Validate for ColorParams {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        match self {
+            ColorParams::BlackAndWhite(params) => params.validate(),
+            ColorParams::ColorOrGrayscale(params) => params.validate(),
+            ColorParams::Mixed(params) => params.validate(),
+        }
+    }
+}
+
+// ScanTailor writes the settings of every mode, so that switching back restores them;
+// settings the mode does not use are kept as they were read
+
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
-pub struct ColorParams {
-    #[serde(rename(serialize = "@colorMode", deserialize = "colorMode"))]
-    pub color_mode: ColorMode,
+pub struct BlackAndWhiteParams {
+    /// not used in black and white
+    #[serde(rename = "color-or-grayscale")]
+    #[validate]
+    pub color_or_grayscale: ColorOrGrayscale,
 
+    #[validate]
+    pub bw: BW,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct ColorOrGrayscaleParams {
     #[serde(rename = "color-or-grayscale")]
     #[validate]
     pub color_or_grayscale: ColorOrGrayscale,
 
+    /// not used in color or grayscale, and not always written
+    #[serde(skip_serializing_if = "Option::is_none")]
     #[validate]
     pub bw: Option<BW>,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MixedParams {
+    #[serde(rename = "color-or-grayscale")]
+    #[validate]
+    pub color_or_grayscale: ColorOrGrayscale,
+
+    #[validate]
+    pub bw: BW,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Eq, PartialEq, Debug)]
 pub enum ColorMode {
     #[serde(rename = "bw")]
     BlackAndWhite,
@@ -28,25 +166,25 @@ pub enum ColorMode {
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct ColorOrGrayscale {
-    #[serde(rename(serialize = "@fillMargins", deserialize = "fillMargins"))]
+    #[serde(rename = "@fillMargins")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub fill_margins: bool,
 
-    #[serde(rename(serialize = "@fillOffcut", deserialize = "fillOffcut"))]
+    #[serde(rename = "@fillOffcut")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub fill_offcut: bool,
 
-    #[serde(rename(serialize = "@fillingColor", deserialize = "fillingColor"))]
+    #[serde(rename = "@fillingColor")]
     pub filling_color: FillingColor,
 
-    #[serde(rename(
-        serialize = "@normalizeIlluminationColor",
-        deserialize = "normalizeIlluminationColor"
-    ))]
+    #[serde(rename = "@normalizeIlluminationColor")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub normalize_illumination_color: bool,
 
     #[serde(rename = "posterization-options")]
@@ -54,7 +192,7 @@ pub struct ColorOrGrayscale {
     pub posterization_options: PosterizationOptions,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub enum FillingColor {
     Background,
@@ -63,78 +201,76 @@ pub enum FillingColor {
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct PosterizationOptions {
-    #[serde(rename(serialize = "@enabled"))]
+    #[serde(rename = "@enabled")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub enabled: bool,
 
-    #[serde(rename(serialize = "@forceBlackAndWhite", deserialize = "forceBlackAndWhite"))]
+    #[serde(rename = "@forceBlackAndWhite")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub force_black_and_white: bool,
 
-    #[serde(rename(serialize = "@level"))]
+    #[serde(rename = "@level")]
     #[validate(range(min = 2))]
     pub level: u8,
 
-    #[serde(rename(
-        serialize = "@normalizationEnabled",
-        deserialize = "normalizationEnabled"
-    ))]
+    #[serde(rename = "@normalizationEnabled")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub normalization_enabled: bool,
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct BW {
-    #[serde(rename(serialize = "@binarizationMethod", deserialize = "binarizationMethod"))]
+    #[serde(rename = "@binarizationMethod")]
     pub binarization_method: BinarizationMethod,
 
-    #[serde(rename(
-        serialize = "@morphologicalSmoothing",
-        deserialize = "morphologicalSmoothing"
-    ))]
+    #[serde(rename = "@morphologicalSmoothing")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub morphological_smoothing: bool,
 
-    #[serde(rename(
-        serialize = "@normalizeIlluminationBW",
-        deserialize = "normalizeIlluminationBW"
-    ))]
+    #[serde(rename = "@normalizeIlluminationBW")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub normalize_illumination_bw: bool,
 
-    #[serde(rename(serialize = "@sauvolaCoef", deserialize = "sauvolaCoef"))]
+    #[serde(rename = "@sauvolaCoef")]
     #[validate(range(min = 0.01, max = 9.99))]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub sauvola_coeff: f64,
 
-    #[serde(rename(
-        serialize = "@savitzkyGolaySmoothing",
-        deserialize = "savitzkyGolaySmoothing"
-    ))]
+    #[serde(rename = "@savitzkyGolaySmoothing")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub savitzky_golay_smoothing: bool,
 
-    #[serde(rename(serialize = "@thresholdAdj", deserialize = "thresholdAdj"))]
+    #[serde(rename = "@thresholdAdj")]
     #[validate(range(min = -100, max = 100))]
     pub threshold_adj: i8,
 
-    #[serde(rename(serialize = "@windowSize", deserialize = "windowSize"))]
+    #[serde(rename = "@windowSize")]
     #[validate(range(min = 3, max = 9999))]
     pub window_size: u16,
 
-    #[serde(rename(serialize = "@wolfCoef", deserialize = "wolfCoef"))]
+    #[serde(rename = "@wolfCoef")]
     #[validate(range(min = 0.01, max = 9.99))]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub wolf_coeff: f64,
 
-    #[serde(rename(serialize = "@wolfLowerBound", deserialize = "wolfLowerBound"))]
+    #[serde(rename = "@wolfLowerBound")]
     #[validate(range(min = 1, max = 254))]
     pub wolf_lower_bound: u8,
 
-    #[serde(rename(serialize = "@wolfUpperBound", deserialize = "wolfUpperBound"))]
+    #[serde(rename = "@wolfUpperBound")]
     #[validate(range(min = 1, max = 254))]
     pub wolf_upper_bound: u8,
 
@@ -143,7 +279,7 @@ pub struct BW {
     pub color_segmenter_options: ColorSegmenterOptions,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum BinarizationMethod {
     Otsu,
@@ -155,45 +291,38 @@ pub enum BinarizationMethod {
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct ColorSegmenterOptions {
-    #[serde(rename(
-        serialize = "@blueThresholdAdjustment",
-        deserialize = "blueThresholdAdjustment"
-    ))]
+    #[serde(rename = "@blueThresholdAdjustment")]
     #[validate(range(min = -99, max = 99))]
     pub blue_threshold_adjustment: i8,
 
-    #[serde(rename(serialize = "@enabled"))]
+    #[serde(rename = "@enabled")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub enabled: bool,
 
-    #[serde(rename(
-        serialize = "@greenThresholdAdjustment",
-        deserialize = "greenThresholdAdjustment"
-    ))]
+    #[serde(rename = "@greenThresholdAdjustment")]
     #[validate(range(min = -99, max = 99))]
     pub green_threshold_adjustment: i8,
 
-    #[serde(rename(serialize = "@noiseReduction", deserialize = "noiseReduction"))]
+    #[serde(rename = "@noiseReduction")]
     #[validate(range(min = 0, max = 999))]
     pub noise_reduction: u16,
 
-    #[serde(rename(
-        serialize = "@redThresholdAdjustment",
-        deserialize = "redThresholdAdjustment"
-    ))]
+    #[serde(rename = "@redThresholdAdjustment")]
     #[validate(range(min = -99, max = 99))]
     pub red_threshold_adjustment: i8,
 }
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::{de::from_str, se::to_string};
 
     use crate::{
         filters::output::common::color_params::{BinarizationMethod, ColorMode, FillingColor},
+        project::test::ORIGINAL_CONTENT,
         ProjectXML,
     };
 
@@ -219,7 +348,7 @@ mod test {
             (MIXED, ColorMode::Mixed),
         ] {
             let color_params = get_color_params(content);
-            assert_eq!(color_params.color_mode, expected_mode);
+            assert_eq!(color_params.color_mode(), expected_mode);
         }
     }
 
@@ -236,7 +365,7 @@ mod test {
         ] {
             let color_params = get_color_params(content);
             assert_eq!(
-                color_params.color_or_grayscale.filling_color,
+                color_params.color_or_grayscale().unwrap().filling_color,
                 expected_color
             );
         }
@@ -261,9 +390,57 @@ mod test {
         ] {
             let color_params = get_color_params(content);
             assert_eq!(
-                color_params.bw.unwrap().binarization_method,
+                color_params.bw().unwrap().binarization_method,
                 expected_method
             );
         }
     }
+
+    #[test]
+    fn it_keeps_settings_the_mode_does_not_use() {
+        for mode in ["colorOrGray", "bw", "mixed"] {
+            let content = ORIGINAL_CONTENT.replace(
+                r#"colorMode="colorOrGray""#,
+                &format!(r#"colorMode="{}""#, mode),
+            );
+            let project: ProjectXML = from_str(&content).unwrap();
+            let color_params = &project.filters.output.page[0].params.color_params;
+            assert_eq!(
+                color_params.bw().is_some(),
+                mode != "colorOrGray",
+                "{}",
+                mode
+            );
+            assert_eq!(
+                color_params.color_or_grayscale().is_some(),
+                mode != "bw",
+                "{}",
+                mode
+            );
+            assert_eq!(to_string(&project).unwrap(), content);
+        }
+    }
+
+    #[test]
+    fn it_requires_the_settings_of_the_mode() {
+        let start = ORIGINAL_CONTENT.find("<bw binarizationMethod").unwrap();
+        let end = ORIGINAL_CONTENT.find("</bw>").unwrap() + "</bw>".len();
+        let content = format!("{}{}", &ORIGINAL_CONTENT[..start], &ORIGINAL_CONTENT[end..]);
+
+        let project: ProjectXML = from_str(&content).unwrap();
+        assert!(matches!(
+            &project.filters.output.page[0].params.color_params,
+            ColorParams::ColorOrGrayscale(params) if params.bw.is_none()
+        ));
+        assert_eq!(to_string(&project).unwrap(), content);
+
+        for mode in ["bw", "mixed"] {
+            let content = content.replacen(
+                r#"colorMode="colorOrGray""#,
+                &format!(r#"colorMode="{}""#, mode),
+                1,
+            );
+            assert!(from_str::<ProjectXML>(&content).is_err(), "{}", mode);
+        }
+    }
 }
@@ -0,0 +1,573 @@
+use crate::{common::Point, units::Px};
+
+/// A page curled like the side of a cylinder, seen between its top and bottom curves,
+/// the directrices. The model is the one of ScanTailor's `CylindricalSurfaceDewarper`.
+///
+/// The ends of the curves make the corners of a plane the page would lie on if it were
+/// flat. Lines across the plane from the top to the bottom, the generatrices, stay straight
+/// on the page; each one is mapped onto the image by a one dimensional homography through
+/// where it meets the curves and where it meets the one line across the page that stays
+/// straight in the image. How much longer or shorter generatrices look between the curves
+/// than on the plane tells how far the page rises or sinks there, which
+/// `depth_perception` scales, and the page is unrolled along the arc that makes.
+///
+/// Meshes are only guaranteed to follow this model, which is enough to tell roughly where
+/// an output point comes from. Reproducing the exact source points ScanTailor samples, so
+/// that its output can be regenerated elsewhere, needs them checked against meshes taken
+/// from a ScanTailor run, which has not been done.
+#[derive(Clone, Debug)]
+pub struct CylindricalSurface {
+    top: Vec<Point>,
+    bottom: Vec<Point>,
+    plane_to_image: Homography,
+    image_to_plane: Homography,
+    /// plane y of the line across the page that stays straight in the image
+    straight_line_y: f64,
+    arc_lengths: ArcLengthMapper,
+}
+
+/// Source points of output points spread evenly across the output, row by row, as the
+/// model of [`CylindricalSurface`] places them.
+#[derive(Clone, PartialEq, Debug)]
+pub struct DewarpingMesh {
+    pub columns: usize,
+    pub rows: usize,
+    pub points: Vec<Point>,
+}
+
+impl CylindricalSurface {
+    /// Sets the surface up from curves going across the page, whichever way they were
+    /// traced.
+    pub fn new(
+        top: &[Point],
+        bottom: &[Point],
+        depth_perception: f64,
+    ) -> Result<Self, &'static str> {
+        if top.len() < 2 || bottom.len() < 2 {
+            return Err("curves need at least 2 points");
+        }
+        let left_to_right = |curve: &[Point]| {
+            let mut curve = curve.to_vec();
+            if curve[0].x > curve[curve.len() - 1].x {
+                curve.reverse();
+            }
+            curve
+        };
+        let (top, bottom) = (left_to_right(top), left_to_right(bottom));
+        let plane_to_image = Homography::from_unit_square([
+            top[0],
+            top[top.len() - 1],
+            bottom[bottom.len() - 1],
+            bottom[0],
+        ])
+        .ok_or("curves do not span an area")?;
+        let image_to_plane = plane_to_image
+            .inverse()
+            .ok_or("curves do not span an area")?;
+
+        let mut surface = CylindricalSurface {
+            top,
+            bottom,
+            plane_to_image,
+            image_to_plane,
+            straight_line_y: 0.5,
+            arc_lengths: ArcLengthMapper::default(),
+        };
+        let pairs = surface.coupled_points();
+        surface.straight_line_y = surface.straight_line_y(&pairs);
+
+        let mut previous_x = f64::MIN;
+        for (top, bottom, x) in pairs {
+            // an S shaped page folds back on itself, which cannot be unrolled
+            if x <= previous_x {
+                continue;
+            }
+            let generatrix = (top, bottom);
+            let y0 = projection(&generatrix, surface.to_image(x, 0f64));
+            let y1 = projection(&generatrix, surface.to_image(x, 1f64));
+            let elevation = (depth_perception * (1f64 - (y1 - y0))).clamp(-0.5, 0.5);
+            surface.arc_lengths.add_sample(x, elevation);
+            previous_x = x;
+        }
+        surface.arc_lengths.normalize();
+        Ok(surface)
+    }
+
+    /// The source point shown at `x` across and `y` down the output, both from 0 to 1,
+    /// unless the curves do not reach across the page there.
+    pub fn map_to_source(&self, x: f64, y: f64) -> Option<Point> {
+        let x = self.arc_lengths.x(x);
+        let (top, bottom) = (self.to_image(x, 0f64), self.to_image(x, 1f64));
+        let generatrix = (top, bottom);
+        let top_y = projection(&generatrix, intersect(&self.top, &generatrix)?);
+        let bottom_y = projection(&generatrix, intersect(&self.bottom, &generatrix)?);
+
+        let middle =
+            if self.straight_line_y.abs() < 0.05 || (self.straight_line_y - 1f64).abs() < 0.05 {
+                (0.5, (top_y + bottom_y) / 2f64)
+            } else {
+                let straight = self.to_image(x, self.straight_line_y);
+                (self.straight_line_y, projection(&generatrix, straight))
+            };
+        let t = three_point_homography([(0f64, top_y), (1f64, bottom_y), middle])(y);
+        Some(Point::new(
+            top.x + (bottom.x - top.x) * t,
+            top.y + (bottom.y - top.y) * t,
+        ))
+    }
+
+    /// The source points of `columns` by `rows` output points, from corner to corner,
+    /// unless the curves do not reach across the page.
+    pub fn mesh(&self, columns: usize, rows: usize) -> Option<DewarpingMesh> {
+        let (columns, rows) = (columns.max(2), rows.max(2));
+        let points = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (column, row)))
+            .map(|(column, row)| {
+                self.map_to_source(
+                    column as f64 / (columns - 1) as f64,
+                    row as f64 / (rows - 1) as f64,
+                )
+            })
+            .collect::<Option<_>>()?;
+        Some(DewarpingMesh {
+            columns,
+            rows,
+            points,
+        })
+    }
+
+    fn to_image(&self, x: f64, y: f64) -> Point {
+        let (x, y) = self.plane_to_image.map((x, y));
+        Point::new(Px(x), Px(y))
+    }
+
+    fn plane_x(&self, point: Point) -> f64 {
+        self.image_to_plane.map((point.x.0, point.y.0)).0
+    }
+
+    /// Walks both curves at once, in order of plane x, pairing every point of either
+    /// curve with where the generatrix through it meets the other one.
+    fn coupled_points(&self) -> Vec<(Point, Point, f64)> {
+        let (top, bottom) = (&self.top, &self.bottom);
+        let (mut i, mut j) = (0, 0);
+        let (mut previous_top, mut previous_bottom) = (top[0], bottom[0]);
+        let (mut next_top_x, mut next_bottom_x) = (self.plane_x(top[0]), self.plane_x(bottom[0]));
+
+        let mut pairs = vec![];
+        while i < top.len() || j < bottom.len() {
+            if i < top.len() && (next_top_x < next_bottom_x || j == bottom.len()) {
+                let point = top[i];
+                let x = next_top_x;
+                let generatrix = (point, self.to_image(x, 1f64));
+                let segment = (previous_bottom, bottom[j.min(bottom.len() - 1)]);
+                let other = line_intersection(&generatrix, &segment).unwrap_or(segment.1);
+                pairs.push((point, other, x));
+                previous_top = point;
+                i += 1;
+                if i < top.len() {
+                    next_top_x = self.plane_x(top[i]);
+                }
+            } else {
+                let point = bottom[j];
+                let x = next_bottom_x;
+                let generatrix = (self.to_image(x, 0f64), point);
+                let segment = (previous_top, top[i.min(top.len() - 1)]);
+                let other = line_intersection(&generatrix, &segment).unwrap_or(segment.1);
+                pairs.push((other, point, x));
+                previous_bottom = point;
+                j += 1;
+                if j < bottom.len() {
+                    next_bottom_x = self.plane_x(bottom[j]);
+                }
+            }
+        }
+        pairs
+    }
+
+    /// The plane y of the line across the page that stays straight in the image, where
+    /// the curves bend away from the edges of the plane in proportion, on average.
+    fn straight_line_y(&self, pairs: &[(Point, Point, f64)]) -> f64 {
+        let (mut sum, mut weights) = (0f64, 0f64);
+        for (top, bottom, x) in pairs {
+            if top == bottom {
+                continue;
+            }
+            let generatrix = (*top, *bottom);
+            let y0 = projection(&generatrix, self.to_image(*x, 0f64));
+            let y1 = projection(&generatrix, self.to_image(*x, 1f64));
+            let (top_bend, bottom_bend) = (y0, 1f64 - y1);
+            let weight = (top_bend + bottom_bend).abs();
+            if weight < 0.01 {
+                continue;
+            }
+            let t = (y1 * top_bend + y0 * bottom_bend) / (top_bend + bottom_bend);
+            let point = Point::new(
+                top.x + (bottom.x - top.x) * t,
+                top.y + (bottom.y - top.y) * t,
+            );
+            sum += self.image_to_plane.map((point.x.0, point.y.0)).1 * weight;
+            weights += weight;
+        }
+        if weights == 0f64 {
+            0.5
+        } else {
+            sum / weights
+        }
+    }
+}
+
+/// The unrolled length of the page up to a plane x, from samples of how far the page
+/// rises or sinks there.
+#[derive(Clone, Default, Debug)]
+struct ArcLengthMapper {
+    /// plane x and arc length up to it
+    samples: Vec<(f64, f64)>,
+    previous_elevation: f64,
+}
+
+impl ArcLengthMapper {
+    fn add_sample(&mut self, x: f64, elevation: f64) {
+        let length = match self.samples.last() {
+            Some((previous_x, length)) => {
+                length + (x - previous_x).hypot(elevation - self.previous_elevation)
+            }
+            None => 0f64,
+        };
+        self.samples.push((x, length));
+        self.previous_elevation = elevation;
+    }
+
+    /// Scales the arc lengths to go from 0 to 1.
+    fn normalize(&mut self) {
+        let Some(&(_, total)) = self.samples.last() else {
+            return;
+        };
+        if total == 0f64 {
+            return;
+        }
+        for (_, length) in self.samples.iter_mut() {
+            *length /= total;
+        }
+    }
+
+    /// The plane x of an arc length, extending the end segments beyond the samples.
+    fn x(&self, length: f64) -> f64 {
+        match self.samples.len() {
+            0 => return 0f64,
+            1 => return self.samples[0].0,
+            _ => {}
+        }
+        let segment = self
+            .samples
+            .partition_point(|(_, sample)| *sample < length)
+            .clamp(1, self.samples.len() - 1);
+        let ((x0, length0), (x1, length1)) = (self.samples[segment - 1], self.samples[segment]);
+        if length1 == length0 {
+            return x0;
+        }
+        x0 + (length - length0) * (x1 - x0) / (length1 - length0)
+    }
+}
+
+/// Where along a line a point projects to, 0 being its first point and 1 its second.
+fn projection(line: &(Point, Point), point: Point) -> f64 {
+    let (a, b) = line;
+    let (dx, dy) = ((b.x - a.x).0, (b.y - a.y).0);
+    ((point.x - a.x).0 * dx + (point.y - a.y).0 * dy) / (dx * dx + dy * dy)
+}
+
+/// Where two lines through two points each meet, unless they are parallel.
+fn line_intersection(a: &(Point, Point), b: &(Point, Point)) -> Option<Point> {
+    let (da, db) = (
+        (a.1.x - a.0.x, a.1.y - a.0.y),
+        (b.1.x - b.0.x, b.1.y - b.0.y),
+    );
+    let denominator = da.0 .0 * db.1 .0 - da.1 .0 * db.0 .0;
+    if denominator == 0f64 {
+        return None;
+    }
+    let t = ((b.0.x - a.0.x).0 * db.1 .0 - (b.0.y - a.0.y).0 * db.0 .0) / denominator;
+    Some(Point::new(a.0.x + da.0 * t, a.0.y + da.1 * t))
+}
+
+/// The one dimensional homography `t -> (a t + b) / (c t + 1)` through three pairs,
+/// the first of which maps 0. It is linear through the first two if the third is on
+/// neither side of them.
+fn three_point_homography(pairs: [(f64, f64); 3]) -> impl Fn(f64) -> f64 {
+    let [(_, y0), (_, y1), (s, ys)] = pairs;
+    let denominator = s * (y1 - ys);
+    let c = if denominator == 0f64 {
+        0f64
+    } else {
+        (ys - y0 - s * (y1 - y0)) / denominator
+    };
+    let a = y1 - y0 + c * y1;
+    move |t| (a * t + y0) / (c * t + 1f64)
+}
+
+impl DewarpingMesh {
+    pub fn point(&self, column: usize, row: usize) -> Point {
+        self.points[row * self.columns + column]
+    }
+
+    /// The source point shown at `x` across and `y` down the output, both from 0 to 1,
+    /// interpolated between the points of the mesh.
+    pub fn map_to_source(&self, x: f64, y: f64) -> Point {
+        let locate = |t: f64, count: usize| {
+            let t = t.clamp(0f64, 1f64) * (count - 1) as f64;
+            let index = (t.floor() as usize).min(count - 2);
+            (index, t - index as f64)
+        };
+        let ((column, fx), (row, fy)) = (locate(x, self.columns), locate(y, self.rows));
+        let lerp =
+            |a: Point, b: Point, t: f64| Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t);
+        lerp(
+            lerp(self.point(column, row), self.point(column + 1, row), fx),
+            lerp(
+                self.point(column, row + 1),
+                self.point(column + 1, row + 1),
+                fx,
+            ),
+            fy,
+        )
+    }
+}
+
+/// Where a line through two points meets a curve, extending the end segments of the
+/// curve if it does not reach that far.
+fn intersect(curve: &[Point], line: &(Point, Point)) -> Option<Point> {
+    let (a, b) = line;
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    // which side of the line a point is on, scaled by its distance
+    let side = |point: &Point| dx.0 * (point.y - a.y).0 - dy.0 * (point.x - a.x).0;
+    let crossing = |p: &Point, q: &Point| {
+        let (side_p, side_q) = (side(p), side(q));
+        (side_p != side_q).then(|| {
+            let t = side_p / (side_p - side_q);
+            (t, Point::new(p.x + (q.x - p.x) * t, p.y + (q.y - p.y) * t))
+        })
+    };
+
+    let segments = curve.windows(2).collect::<Vec<_>>();
+    segments
+        .iter()
+        .filter_map(|segment| crossing(&segment[0], &segment[1]))
+        .find(|(t, _)| (0f64..=1f64).contains(t))
+        .or_else(|| crossing(&segments[0][0], &segments[0][1]).filter(|(t, _)| *t < 0f64))
+        .or_else(|| {
+            let last = segments[segments.len() - 1];
+            crossing(&last[0], &last[1]).filter(|(t, _)| *t > 1f64)
+        })
+        .map(|(_, point)| point)
+}
+
+/// A projective mapping of the plane, as a row major 3x3 matrix applied to `(x, y, 1)`.
+#[derive(Clone, Copy, Debug)]
+struct Homography([[f64; 3]; 3]);
+
+impl Homography {
+    /// Maps the unit square to a quadrilateral, corners going from (0, 0) to (1, 0),
+    /// (1, 1) and (0, 1), unless three of them are on a line.
+    fn from_unit_square(corners: [Point; 4]) -> Option<Self> {
+        let [(x0, y0), (x1, y1), (x2, y2), (x3, y3)] =
+            corners.map(|corner| (corner.x.0, corner.y.0));
+        let (sx, sy) = (x0 - x1 + x2 - x3, y0 - y1 + y2 - y3);
+        let (dx1, dx2, dy1, dy2) = (x1 - x2, x3 - x2, y1 - y2, y3 - y2);
+        let denominator = dx1 * dy2 - dx2 * dy1;
+        if denominator == 0f64 {
+            return None;
+        }
+        let g = (sx * dy2 - dx2 * sy) / denominator;
+        let h = (dx1 * sy - sx * dy1) / denominator;
+        let homography = Homography([
+            [x1 - x0 + g * x1, x3 - x0 + h * x3, x0],
+            [y1 - y0 + g * y1, y3 - y0 + h * y3, y0],
+            [g, h, 1f64],
+        ]);
+        (homography.determinant() != 0f64).then_some(homography)
+    }
+
+    fn determinant(&self) -> f64 {
+        let m = &self.0;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+
+    /// The adjugate, which undoes the mapping as well as the inverse does.
+    fn inverse(&self) -> Option<Self> {
+        if self.determinant() == 0f64 {
+            return None;
+        }
+        let m = &self.0;
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+        Some(Homography([
+            [
+                cofactor(1, 2, 1, 2),
+                -cofactor(0, 2, 1, 2),
+                cofactor(0, 1, 1, 2),
+            ],
+            [
+                -cofactor(1, 2, 0, 2),
+                cofactor(0, 2, 0, 2),
+                -cofactor(0, 1, 0, 2),
+            ],
+            [
+                cofactor(1, 2, 0, 1),
+                -cofactor(0, 2, 0, 1),
+                cofactor(0, 1, 0, 1),
+            ],
+        ]))
+    }
+
+    fn map(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let m = &self.0;
+        let w = m[2][0] * x + m[2][1] * y + m[2][2];
+        (
+            (m[0][0] * x + m[0][1] * y + m[0][2]) / w,
+            (m[1][0] * x + m[1][1] * y + m[1][2]) / w,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::de::from_str;
+
+    use crate::{common::Point, filters::output::common::DistortionModel, units::Px};
+
+    use super::CylindricalSurface;
+
+    fn assert_near(a: &Point, b: &Point) {
+        assert!(a.distance(b) < Px(1e-6), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn it_keeps_flat_pages_flat() {
+        // a page seen in perspective, narrower at the top
+        let top = [
+            Point::new(Px(100f64), Px(0f64)),
+            Point::new(Px(900f64), Px(0f64)),
+        ];
+        let bottom = [
+            Point::new(Px(0f64), Px(1000f64)),
+            Point::new(Px(1000f64), Px(1000f64)),
+        ];
+        let surface = CylindricalSurface::new(&top, &bottom, 2f64).unwrap();
+        let mesh = surface.mesh(5, 3).unwrap();
+
+        assert_eq!(mesh.points.len(), 15);
+        assert_near(&mesh.point(0, 0), &top[0]);
+        assert_near(&mesh.point(4, 0), &top[1]);
+        assert_near(&mesh.point(0, 2), &bottom[0]);
+        assert_near(&mesh.point(4, 2), &bottom[1]);
+        // the farther top of the page looks smaller, which moves its middle up
+        let middle = Point::new(Px(500f64), Px(4000f64 / 9f64));
+        assert_near(&mesh.point(2, 1), &middle);
+        assert_near(&mesh.map_to_source(0.5, 0.5), &middle);
+        assert_near(
+            &surface.map_to_source(0.5, 1f64).unwrap(),
+            &Point::new(Px(500f64), Px(1000f64)),
+        );
+
+        assert!(CylindricalSurface::new(&top, &top, 2f64).is_err());
+    }
+
+    #[test]
+    fn it_rejects_pages_the_curves_do_not_cross() {
+        // the curves meet at the left, leaving nothing to dewarp there
+        let top = [
+            Point::new(Px(0f64), Px(0f64)),
+            Point::new(Px(1000f64), Px(0f64)),
+        ];
+        let bottom = [
+            Point::new(Px(0f64), Px(0f64)),
+            Point::new(Px(1000f64), Px(1000f64)),
+        ];
+        assert!(CylindricalSurface::new(&top, &bottom, 2f64).is_err());
+    }
+
+    #[test]
+    fn it_unrolls_curled_pages() {
+        // the page sinks towards the spine, in the middle, where it looks shorter
+        let curve = |y: f64, sag: f64| {
+            (0..=20)
+                .map(|i| {
+                    let x = i as f64 / 20f64;
+                    Point::new(
+                        Px(x * 1000f64),
+                        Px(y + sag * (std::f64::consts::PI * x).sin()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let (top, bottom) = (curve(0f64, 50f64), curve(1000f64, -50f64));
+
+        let shallow = CylindricalSurface::new(&top, &bottom, 1f64).unwrap();
+        let deep = CylindricalSurface::new(&top, &bottom, 3f64).unwrap();
+        for surface in [&shallow, &deep] {
+            // the generatrix in the middle stays in the middle, from curve to curve
+            assert_near(
+                &surface.map_to_source(0.5, 0f64).unwrap(),
+                &Point::new(Px(500f64), Px(50f64)),
+            );
+            assert_near(
+                &surface.map_to_source(0.5, 1f64).unwrap(),
+                &Point::new(Px(500f64), Px(950f64)),
+            );
+            // the curves are followed
+            let point = surface.map_to_source(0.3, 0f64).unwrap();
+            let expected = 50f64 * (std::f64::consts::PI * point.x.0 / 1000f64).sin();
+            assert!((point.y.0 - expected).abs() < 1f64);
+        }
+        // where the page slopes the most it takes more of the output once unrolled, the
+        // deeper the more
+        let (a, b) = (
+            shallow.map_to_source(0.25, 0.5).unwrap(),
+            deep.map_to_source(0.25, 0.5).unwrap(),
+        );
+        assert!(a.x < Px(250f64) && b.x < a.x);
+
+        // the curves can be traced either way
+        let reversed = bottom.iter().rev().copied().collect::<Vec<_>>();
+        let surface = CylindricalSurface::new(&top, &reversed, 1f64).unwrap();
+        assert_eq!(surface.mesh(9, 9), shallow.mesh(9, 9));
+        assert!(surface.mesh(9, 9).is_some());
+    }
+
+    #[test]
+    fn it_keeps_the_line_where_the_page_does_not_bend_straight() {
+        // the bottom bends up twice as much as the top bends down, so the page is not
+        // bent a third of the way down
+        let curve = |y: f64, sag: f64| {
+            (0..=20)
+                .map(|i| {
+                    let x = i as f64 / 20f64;
+                    Point::new(
+                        Px(x * 1000f64),
+                        Px(y + sag * (std::f64::consts::PI * x).sin()),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let surface =
+            CylindricalSurface::new(&curve(0f64, 50f64), &curve(1000f64, -100f64), 2f64).unwrap();
+
+        for x in [0f64, 0.2, 0.5, 0.7, 1f64] {
+            let point = surface.map_to_source(x, 1f64 / 3f64).unwrap();
+            assert!((point.y.0 - 1000f64 / 3f64).abs() < 1e-6, "{:?}", point);
+        }
+    }
+
+    #[test]
+    fn it_meshes_distortion_models() {
+        const CONTENT: &str = r#"<distortion-model><top-curve><polyline>4QKnQ9eiwUNGICJFuyu/Qw==</polyline></top-curve><bottom-curve><polyline>3rOqQzq0a0VmliJFVmVrRQ==</polyline></bottom-curve></distortion-model>"#;
+        let model: DistortionModel = from_str(CONTENT).unwrap();
+        let (top, bottom) = model.curves();
+        let mesh = model.surface(2f64).unwrap().mesh(3, 3).unwrap();
+        assert_near(&mesh.point(0, 0), &top[0]);
+        assert_near(&mesh.point(2, 2), &bottom[1]);
+    }
+}
@@ -1,25 +1,31 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, BoolFromInt};
 use validator::Validate;
 
+use crate::number::QtNumber;
+
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct DewarpingOptions {
-    #[serde(rename(serialize = "@mode"))]
+    #[serde(rename = "@mode")]
     pub mode: DewarpingMode,
 
-    #[serde(rename(serialize = "@postDeskew", deserialize = "postDeskew"))]
+    #[serde(rename = "@postDeskew")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub post_deskew: bool,
 
-    #[serde(rename(serialize = "@postDeskewAngle", deserialize = "postDeskewAngle"))]
+    #[serde(rename = "@postDeskewAngle")]
     // unsure if this is the correct range
     #[validate(range(min = -45, max = 45))]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub post_deskew_angle: f64,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum DewarpingMode {
     Off,
@@ -30,7 +36,7 @@ pub enum DewarpingMode {
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
 
     use crate::{filters::output::common::dewarping_options::DewarpingMode, ProjectXML};
 
@@ -1,96 +1,60 @@
 use std::fmt::Debug;
 
-use serde::{de, Deserialize, Serialize};
-use serde_with::{base64::Base64, serde_as};
+use schemars::JsonSchema;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use serde_with::{base64::Base64, serde_as, DeserializeAs, SerializeAs};
+use validator::{Validate, ValidationError};
 
-use super::super::super::super::common::Point;
+use crate::{units::Px, validation::invalid};
+
+use super::{super::super::super::common::Point, CylindricalSurface};
+
+/// ScanTailor traces a few hundred points across a page at most.
+pub const MAX_POLYLINE_POINTS: usize = 10_000;
 
 // ScanTailor seems to leave in a distortion-model
 // if dewarping was enabled at some point, even if
 // it is now off, without cleaning up
 
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(untagged, try_from = "Curves")]
 pub enum DistortionModel {
     Auto(AutoDistortionModel),
     Marginal(MarginalDistortionModel),
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
-pub struct AutoDistortionModel {
-    #[serde(rename = "top-curve")]
-    pub top_curve: PolylineCurve,
-
-    #[serde(rename = "bottom-curve")]
-    pub bottom_curve: PolylineCurve,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
+// untagged variants are buffered before they are matched, which loses repeated
+// elements, so the curves are read with everything optional and matched after
+#[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
-pub struct MarginalDistortionModel {
+struct Curves {
     #[serde(rename = "top-curve")]
-    pub top_curve: XSplineAndPolylineCurve,
+    top_curve: Curve,
 
     #[serde(rename = "bottom-curve")]
-    pub bottom_curve: XSplineAndPolylineCurve,
+    bottom_curve: Curve,
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Deserialize)]
 #[serde(deny_unknown_fields)]
-pub struct XSplineAndPolylineCurve {
-    pub xspline: XSpline,
+struct Curve {
+    xspline: Option<XSpline>,
 
-    #[serde_as(as = "Base64")]
-    pub polyline: Vec<u8>,
+    #[serde_as(as = "Option<Polyline>")]
+    #[serde(default)]
+    polyline: Option<Vec<Point>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct XSpline {
-    point: Vec<Point>,
-}
-
-#[serde_as]
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(deny_unknown_fields)]
-pub struct PolylineCurve {
-    pub polyline: Vec<u8>,
-}
-
-impl<'de> Deserialize<'de> for DistortionModel {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        #[derive(Serialize, Deserialize, Debug)]
-        #[serde(deny_unknown_fields)]
-        pub struct Model {
-            #[serde(rename = "top-curve")]
-            pub top_curve: Curve,
-
-            #[serde(rename = "bottom-curve")]
-            pub bottom_curve: Curve,
-        }
-
-        #[serde_as]
-        #[derive(Serialize, Deserialize, Debug)]
-        #[serde(deny_unknown_fields)]
-        pub struct Curve {
-            #[serde(skip_serializing_if = "Option::is_none")]
-            pub xspline: Option<XSpline>,
-
-            #[serde(skip_serializing_if = "Option::is_none")]
-            #[serde_as(as = "Option<Base64>")]
-            pub polyline: Option<Vec<u8>>,
-        }
-
-        let m = Model::deserialize(deserializer)?;
+impl TryFrom<Curves> for DistortionModel {
+    type Error = &'static str;
 
+    fn try_from(curves: Curves) -> Result<Self, Self::Error> {
         match (
-            m.top_curve.xspline,
-            m.top_curve.polyline,
-            m.bottom_curve.xspline,
-            m.bottom_curve.polyline,
+            curves.top_curve.xspline,
+            curves.top_curve.polyline,
+            curves.bottom_curve.xspline,
+            curves.bottom_curve.polyline,
         ) {
             (
                 Some(top_xspline),
@@ -117,18 +81,264 @@ impl<'de> Deserialize<'de> for DistortionModel {
                     },
                 }))
             }
-            _ => Err(de::Error::custom("Unknown Curve variant")),
+            _ => Err("unknown curve variant"),
+        }
+    }
+}
+
+impl DistortionModel {
+    /// The top and bottom curves, as the polylines dewarping follows.
+    pub fn curves(&self) -> (&[Point], &[Point]) {
+        match self {
+            DistortionModel::Auto(model) => {
+                (&model.top_curve.polyline, &model.bottom_curve.polyline)
+            }
+            DistortionModel::Marginal(model) => {
+                (&model.top_curve.polyline, &model.bottom_curve.polyline)
+            }
+        }
+    }
+
+    /// The surface to dewarp the page along, see [`CylindricalSurface`].
+    pub fn surface(&self, depth_perception: f64) -> Result<CylindricalSurface, &'static str> {
+        let (top, bottom) = self.curves();
+        CylindricalSurface::new(top, bottom, depth_perception)
+    }
+}
+
+impl Validate for DistortionModel {
+    fn validate(&self) -> Result<(), validator::ValidationErrors> {
+        match self {
+            DistortionModel::Auto(model) => model.validate(),
+            DistortionModel::Marginal(model) => model.validate(),
         }
     }
 }
 
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct AutoDistortionModel {
+    #[serde(rename = "top-curve")]
+    #[validate]
+    pub top_curve: PolylineCurve,
+
+    #[serde(rename = "bottom-curve")]
+    #[validate]
+    pub bottom_curve: PolylineCurve,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct MarginalDistortionModel {
+    #[serde(rename = "top-curve")]
+    #[validate]
+    pub top_curve: XSplineAndPolylineCurve,
+
+    #[serde(rename = "bottom-curve")]
+    #[validate]
+    pub bottom_curve: XSplineAndPolylineCurve,
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct XSplineAndPolylineCurve {
+    pub xspline: XSpline,
+
+    #[serde_as(as = "Polyline")]
+    #[schemars(with = "String")]
+    #[validate(custom = "validate_polyline")]
+    pub polyline: Vec<Point>,
+}
+
+impl From<XSpline> for XSplineAndPolylineCurve {
+    /// Samples the spline once per segment, which is all ScanTailor keeps of the gentle
+    /// curves of a page.
+    fn from(xspline: XSpline) -> Self {
+        XSplineAndPolylineCurve {
+            polyline: xspline.to_polyline(1),
+            xspline,
+        }
+    }
+}
+
+/// An X-spline through the ends of its control polygon and pulled towards the
+/// points in between, as ScanTailor draws the curves of marginal dewarping.
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Default, PartialEq, Debug)]
+pub struct XSpline {
+    /// control points, from one end of the curve to the other
+    pub point: Vec<Point>,
+}
+
+impl XSpline {
+    pub fn new(point: Vec<Point>) -> Self {
+        XSpline { point }
+    }
+
+    /// The shape parameter of a control point: 0 for the ends, which the curve goes
+    /// through, and 1 for the others, which it approximates.
+    pub fn tension(&self, index: usize) -> f64 {
+        if index == 0 || index + 1 == self.point.len() {
+            0f64
+        } else {
+            1f64
+        }
+    }
+
+    pub fn segments(&self) -> usize {
+        self.point.len().saturating_sub(1)
+    }
+
+    /// The point of the curve at `t`, from 0 at its start to 1 at its end, each segment
+    /// between control points taking an equal share, unless there are less than 2
+    /// control points.
+    pub fn point_at(&self, t: f64) -> Option<Point> {
+        let segments = self.segments();
+        if segments == 0 {
+            return None;
+        }
+        let t = t.clamp(0f64, 1f64) * segments as f64;
+        let segment = (t.floor() as usize).min(segments - 1);
+        Some(self.segment_point_at(segment, t - segment as f64))
+    }
+
+    /// The curve sampled evenly `samples` times per segment, along with its end.
+    pub fn to_polyline(&self, samples: usize) -> Vec<Point> {
+        let samples = samples.max(1);
+        let mut polyline = (0..self.segments())
+            .flat_map(|segment| {
+                (0..samples).map(move |sample| (segment, sample as f64 / samples as f64))
+            })
+            .map(|(segment, t)| self.segment_point_at(segment, t))
+            .collect::<Vec<_>>();
+        polyline.extend(self.point.last().filter(|_| self.segments() > 0));
+        polyline
+    }
+
+    /// Blends the control points around a segment after Blanc and Schlick, with the
+    /// neighbours of the ends being the ends themselves.
+    fn segment_point_at(&self, segment: usize, t: f64) -> Point {
+        let last = self.point.len() - 1;
+        let indices = [
+            segment.saturating_sub(1),
+            segment,
+            segment + 1,
+            (segment + 2).min(last),
+        ];
+        let (s1, s2) = (self.tension(segment), self.tension(segment + 1));
+
+        // only shapes from 0 to 1 are used, which approximate the control points
+        let a0 = if t < s1 {
+            f_blend(t - s1, -1f64 - s1)
+        } else {
+            0f64
+        };
+        let a1 = f_blend(t - 1f64 - s2, -1f64 - s2);
+        let a2 = f_blend(t + s1, 1f64 + s1);
+        let a3 = if t > 1f64 - s2 {
+            f_blend(t - 1f64 + s2, 1f64 + s2)
+        } else {
+            0f64
+        };
+
+        let weights = [a0, a1, a2, a3];
+        let sum = weights.iter().sum::<f64>();
+        let (x, y) =
+            indices
+                .iter()
+                .zip(weights)
+                .fold((Px(0f64), Px(0f64)), |(x, y), (index, weight)| {
+                    let point = &self.point[*index];
+                    (x + point.x * weight, y + point.y * weight)
+                });
+        Point::new(x / sum, y / sum)
+    }
+}
+
+/// The blending function of X-splines with positive shapes.
+fn f_blend(numerator: f64, denominator: f64) -> f64 {
+    let p = 2f64 * denominator * denominator;
+    let u = numerator / denominator;
+    u * u * u * (10f64 - p + (2f64 * p - 15f64) * u + (6f64 - p) * u * u)
+}
+
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct PolylineCurve {
+    #[serde_as(as = "Polyline")]
+    #[schemars(with = "String")]
+    #[validate(custom = "validate_polyline")]
+    pub polyline: Vec<Point>,
+}
+
+/// Reads and writes points the way ScanTailor serializes curves: base64 of a `QDataStream`
+/// of little-endian single precision x and y pairs.
+pub struct Polyline;
+
+impl Polyline {
+    pub fn decode(bytes: &[u8]) -> Result<Vec<Point>, &'static str> {
+        if !bytes.len().is_multiple_of(8) {
+            return Err("polyline is not made of x and y pairs");
+        }
+        let float = |bytes: &[u8]| Px(f32::from_le_bytes(bytes.try_into().unwrap()) as f64);
+        Ok(bytes
+            .chunks_exact(8)
+            .map(|pair| Point::new(float(&pair[..4]), float(&pair[4..])))
+            .collect())
+    }
+
+    /// Loses nothing of decoded points, which are single precision to begin with.
+    pub fn encode(points: &[Point]) -> Vec<u8> {
+        points
+            .iter()
+            .flat_map(|point| [point.x.0 as f32, point.y.0 as f32])
+            .flat_map(f32::to_le_bytes)
+            .collect()
+    }
+}
+
+impl SerializeAs<Vec<Point>> for Polyline {
+    fn serialize_as<S: Serializer>(points: &Vec<Point>, serializer: S) -> Result<S::Ok, S::Error> {
+        <Base64 as SerializeAs<Vec<u8>>>::serialize_as(&Polyline::encode(points), serializer)
+    }
+}
+
+impl<'de> DeserializeAs<'de, Vec<Point>> for Polyline {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Point>, D::Error> {
+        let bytes = <Base64 as DeserializeAs<'de, Vec<u8>>>::deserialize_as(deserializer)?;
+        Polyline::decode(&bytes).map_err(de::Error::custom)
+    }
+}
+
+/// Dewarping takes curves as functions of x, so they must go one way across the page.
+fn validate_polyline(points: &[Point]) -> Result<(), ValidationError> {
+    if points.len() < 2 {
+        return Err(invalid(
+            "polyline_too_short",
+            "curve has less than 2 points",
+        ));
+    }
+    if points.len() > MAX_POLYLINE_POINTS {
+        return Err(invalid("polyline_too_long", "curve has too many points"));
+    }
+    let increasing = points.windows(2).all(|pair| pair[0].x < pair[1].x);
+    let decreasing = points.windows(2).all(|pair| pair[0].x > pair[1].x);
+    (increasing || decreasing)
+        .then_some(())
+        .ok_or_else(|| invalid("polyline_not_monotonic", "curve turns back along x"))
+}
+
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::{de::from_str, se::to_string};
+    use validator::Validate;
 
-    use crate::ProjectXML;
+    use crate::{common::Point, units::Px, ProjectXML};
 
-    use super::DistortionModel;
+    use super::{
+        DistortionModel, MarginalDistortionModel, Polyline, PolylineCurve, XSplineAndPolylineCurve,
+    };
 
     fn get_distortion_model(content: &str) -> DistortionModel {
         let project: ProjectXML = from_str(content).unwrap();
@@ -142,19 +352,107 @@ mod test {
     fn it_deserializes_auto_distortion_model() {
         const CONTENT: &str = r#"<project layoutDirection="LTR" outputDirectory="/home/ramzi/src/bill-organizer/out" version="3"><directories><directory id="1" path="/home/ramzi/src/bill-organizer"/></directories><files><file dirId="1" id="2" name="in.jpg"/></files><images><image fileId="2" fileImage="0" id="3" subPages="1"><size height="4961" width="7016"/><dpi horizontal="600" vertical="600"/></image></images><pages><page id="4" imageId="3" selected="selected" subPage="single"/></pages><file-name-disambiguation><mapping file="2" label="0"/></file-name-disambiguation><filters><fix-orientation><image-settings><page id="4"><image-params blackOnWhite="1" bwThreshold="193"/></page></image-settings></fix-orientation><page-split defaultLayoutType="auto-detect"><image id="3" layoutType="single-uncut"><params mode="auto"><pages type="single-uncut"><outline><point x="0" y="0"/><point x="7016" y="0"/><point x="7016" y="4961"/><point x="0" y="4961"/><point x="0" y="0"/></outline></pages><dependencies><rotation degrees="0"/><size height="4961" width="7016"/><layoutType>single-uncut</layoutType></dependencies></params></image></page-split><deskew><page id="4"><params angle="0.125" mode="auto"><dependencies><rotation degrees="0"/><page-outline><point x="0" y="0"/><point x="7016" y="0"/><point x="7016" y="4961"/><point x="0" y="4961"/><point x="0" y="0"/></page-outline></dependencies></params></page><image-settings><page id="4"><image-params blackOnWhite="1" bwThreshold="193"/></page></image-settings></deskew><select-content pageDetectionTolerance="0.1"><page id="4"><params contentDetectionMode="auto" fineTuneCorners="0" pageDetectionMode="disabled"><content-rect height="3384" width="2260" x="344" y="388"/><page-rect height="4976.294719097549" width="7026.806517602445" x="0" y="0"/><content-size-mm height="143.256286512573" width="95.6735246803827"/><dependencies><rotated-page-outline><point x="10.82321443814471" y="0"/><point x="7026.806517602445" y="15.30652539770678"/><point x="7015.9833031643" y="4976.294719097549"/><point x="0" y="4960.988193699843"/><point x="10.82321443814471" y="0"/></rotated-page-outline><params contentDetectionMode="auto" fineTuneCorners="0" pageDetectionMode="disabled"/></dependencies></params></page></select-content><page-layout showMiddleRect="1"><page id="4"><params autoMargins="0"><hardMarginsMM bottom="5" left="10" right="10" top="5"/><pageRect height="4976.294719097549" width="7026.806517602445" x="0" y="0"/><contentRect height="3384" width="2260" x="344" y="388"/><contentSizeMM height="143.256286512573" width="95.6735246803827"/><alignment hor="center" null="0" vert="center"/></params></page></page-layout><output><page id="4"><zones/><fill-zones/><params blackOnWhite="1" depthPerception="2" despeckleLevel="1"><distortion-model><top-curve><polyline>4QKnQ9eiwUNGICJFuyu/Qw==</polyline></top-curve><bottom-curve><polyline>3rOqQzq0a0VmliJFVmVrRQ==</polyline></bottom-curve></distortion-model><picture-shape-options higherSearchSensitivity="0" pictureShape="free" sensitivity="100"/><dewarping-options mode="auto" postDeskew="1" postDeskewAngle="0"/><dpi horizontal="600" vertical="600"/><color-params colorMode="bw"><color-or-grayscale fillMargins="1" fillOffcut="1" fillingColor="background" normalizeIlluminationColor="0"><posterization-options enabled="0" forceBlackAndWhite="1" level="4" normalizationEnabled="0"/></color-or-grayscale><bw binarizationMethod="otsu" morphologicalSmoothing="1" normalizeIlluminationBW="1" sauvolaCoef="0.34" savitzkyGolaySmoothing="1" thresholdAdj="0" windowSize="200" wolfCoef="0.3" wolfLowerBound="1" wolfUpperBound="254"><color-segmenter-options blueThresholdAdjustment="0" enabled="0" greenThresholdAdjustment="0" noiseReduction="7" redThresholdAdjustment="0"/></bw></color-params><splitting originalBackground="0" splitOutput="0" splittingMode="bw"/></params><processing-params autoZonesFound="0" blackOnWhiteSetManually="0"/><output-params><image blackOnWhite="1" depthPerception="2" despeckleLevel="1"><size height="3620" width="2732"/><content-rect height="3384" width="2260" x="236" y="118"/><crop-area><point x="-97.17678556185528" y="-270"/><point x="6918.806517602445" y="-254.6934746022932"/><point x="6907.9833031643" y="4706.294719097549"/><point x="-108" y="4690.988193699843"/><point x="-97.17678556185528" y="-270"/></crop-area><partial-xform><m11>0.9999976201773518</m11><m12>0.00218165983433677</m12><m21>-0.00218165983433677</m21><m22>0.9999976201773518</m22></partial-xform><dpi horizontal="600" vertical="600"/><color-params colorMode="bw"><color-or-grayscale fillMargins="1" fillOffcut="1" fillingColor="background" normalizeIlluminationColor="0"><posterization-options enabled="0" forceBlackAndWhite="1" level="4" normalizationEnabled="0"/></color-or-grayscale><bw binarizationMethod="otsu" morphologicalSmoothing="1" normalizeIlluminationBW="1" sauvolaCoef="0.34" savitzkyGolaySmoothing="1" thresholdAdj="0" windowSize="200" wolfCoef="0.3" wolfLowerBound="1" wolfUpperBound="254"><color-segmenter-options blueThresholdAdjustment="0" enabled="0" greenThresholdAdjustment="0" noiseReduction="7" redThresholdAdjustment="0"/></bw></color-params><splitting originalBackground="0" splitOutput="0" splittingMode="bw"/><picture-shape-options higherSearchSensitivity="0" pictureShape="free" sensitivity="100"/><distortion-model><top-curve><polyline>4QKnQ9eiwUNGICJFuyu/Qw==</polyline></top-curve><bottom-curve><polyline>3rOqQzq0a0VmliJFVmVrRQ==</polyline></bottom-curve></distortion-model><dewarping-options mode="auto" postDeskew="1" postDeskewAngle="0"/><processing-params autoZonesFound="0" blackOnWhiteSetManually="0"/></image><source_file mtime="1698783384" size="2890349"/><file mtime="1699404572" size="26508"/><speckles mtime="1699404572" size="692"/><zones/><fill-zones/></output-params></page></output></filters></project>"#;
         let distortion_model = get_distortion_model(CONTENT);
-        match distortion_model {
-            DistortionModel::Auto(_) => {}
-            _ => assert!(false),
-        }
+        distortion_model.validate().unwrap();
+        let DistortionModel::Auto(model) = distortion_model else {
+            panic!();
+        };
+        assert_eq!(
+            model.top_curve.polyline,
+            vec![
+                Point::new(Px(334.0225f32.into()), Px(387.2722f32.into())),
+                Point::new(Px(2594.017f32.into()), Px(382.34164f32.into())),
+            ]
+        );
+        assert_eq!(
+            to_string(&model.top_curve).unwrap(),
+            "<PolylineCurve><polyline>4QKnQ9eiwUNGICJFuyu/Qw==</polyline></PolylineCurve>"
+        );
     }
 
     #[test]
     fn it_deserializes_marginal_distortion_model() {
         const CONTENT: &str = r#"<project layoutDirection="LTR" outputDirectory="/home/ramzi/src/bill-organizer/out" version="3"><directories><directory id="1" path="/home/ramzi/src/bill-organizer"/></directories><files><file dirId="1" id="2" name="in.jpg"/></files><images><image fileId="2" fileImage="0" id="3" subPages="1"><size height="4961" width="7016"/><dpi horizontal="600" vertical="600"/></image></images><pages><page id="4" imageId="3" selected="selected" subPage="single"/></pages><file-name-disambiguation><mapping file="2" label="0"/></file-name-disambiguation><filters><fix-orientation><image-settings><page id="4"><image-params blackOnWhite="1" bwThreshold="193"/></page></image-settings></fix-orientation><page-split defaultLayoutType="auto-detect"><image id="3" layoutType="single-uncut"><params mode="auto"><pages type="single-uncut"><outline><point x="0" y="0"/><point x="7016" y="0"/><point x="7016" y="4961"/><point x="0" y="4961"/><point x="0" y="0"/></outline></pages><dependencies><rotation degrees="0"/><size height="4961" width="7016"/><layoutType>single-uncut</layoutType></dependencies></params></image></page-split><deskew><page id="4"><params angle="0.125" mode="auto"><dependencies><rotation degrees="0"/><page-outline><point x="0" y="0"/><point x="7016" y="0"/><point x="7016" y="4961"/><point x="0" y="4961"/><point x="0" y="0"/></page-outline></dependencies></params></page><image-settings><page id="4"><image-params blackOnWhite="1" bwThreshold="193"/></page></image-settings></deskew><select-content pageDetectionTolerance="0.1"><page id="4"><params contentDetectionMode="auto" fineTuneCorners="0" pageDetectionMode="disabled"><content-rect height="3384" width="2260" x="344" y="388"/><page-rect height="4976.294719097549" width="7026.806517602445" x="0" y="0"/><content-size-mm height="143.256286512573" width="95.6735246803827"/><dependencies><rotated-page-outline><point x="10.82321443814471" y="0"/><point x="7026.806517602445" y="15.30652539770678"/><point x="7015.9833031643" y="4976.294719097549"/><point x="0" y="4960.988193699843"/><point x="10.82321443814471" y="0"/></rotated-page-outline><params contentDetectionMode="auto" fineTuneCorners="0" pageDetectionMode="disabled"/></dependencies></params></page></select-content><page-layout showMiddleRect="1"><page id="4"><params autoMargins="0"><hardMarginsMM bottom="5" left="10" right="10" top="5"/><pageRect height="4976.294719097549" width="7026.806517602445" x="0" y="0"/><contentRect height="3384" width="2260" x="344" y="388"/><contentSizeMM height="143.256286512573" width="95.6735246803827"/><alignment hor="center" null="0" vert="center"/></params></page></page-layout><output><page id="4"><zones/><fill-zones/><params blackOnWhite="1" depthPerception="2" despeckleLevel="1"><distortion-model><top-curve><xspline><point x="334.0224766759178" y="14"/><point x="2204.362853173144" y="0"/><point x="2282.293702193861" y="0"/><point x="2360.224551214579" y="0"/><point x="2438.155400235297" y="0"/><point x="2516.086249256015" y="0"/><point x="2594.017098276733" y="0"/></xspline><polyline>4QKnQwAAYEEYNO5EVVUVQLOkDkUAAAAAmIMTRQAAAAB9YhhFAAAAAGFBHUUAAAAARiAiRQAAAAA=</polyline></top-curve><bottom-curve><xspline><point x="341.4052135553135" y="4960"/><point x="2204.362853173144" y="4960"/><point x="2282.293702193861" y="4960"/><point x="2360.224551214579" y="4960"/><point x="2438.155400235297" y="4960"/><point x="2516.086249256015" y="4960"/><point x="2601.399835156129" y="4960"/></xspline><polyline>3rOqQwAAm0V4W+5EAACbRbOkDkUAAJtFmIMTRQAAm0V9YhhFAACbRRFVHUUAAJtFZpYiRQAAm0U=</polyline></bottom-curve></distortion-model><picture-shape-options higherSearchSensitivity="0" pictureShape="free" sensitivity="100"/><dewarping-options mode="marginal" postDeskew="1" postDeskewAngle="0"/><dpi horizontal="600" vertical="600"/><color-params colorMode="bw"><color-or-grayscale fillMargins="1" fillOffcut="1" fillingColor="background" normalizeIlluminationColor="0"><posterization-options enabled="0" forceBlackAndWhite="1" level="4" normalizationEnabled="0"/></color-or-grayscale><bw binarizationMethod="otsu" morphologicalSmoothing="1" normalizeIlluminationBW="1" sauvolaCoef="0.34" savitzkyGolaySmoothing="1" thresholdAdj="0" windowSize="200" wolfCoef="0.3" wolfLowerBound="1" wolfUpperBound="254"><color-segmenter-options blueThresholdAdjustment="0" enabled="0" greenThresholdAdjustment="0" noiseReduction="7" redThresholdAdjustment="0"/></bw></color-params><splitting originalBackground="0" splitOutput="0" splittingMode="bw"/></params><processing-params autoZonesFound="0" blackOnWhiteSetManually="0"/><output-params><image blackOnWhite="1" depthPerception="2" despeckleLevel="1"><size height="3620" width="2732"/><content-rect height="3384" width="2260" x="236" y="118"/><crop-area><point x="-97.17678556185528" y="-270"/><point x="6918.806517602445" y="-254.6934746022932"/><point x="6907.9833031643" y="4706.294719097549"/><point x="-108" y="4690.988193699843"/><point x="-97.17678556185528" y="-270"/></crop-area><partial-xform><m11>0.9999976201773518</m11><m12>0.00218165983433677</m12><m21>-0.00218165983433677</m21><m22>0.9999976201773518</m22></partial-xform><dpi horizontal="600" vertical="600"/><color-params colorMode="bw"><color-or-grayscale fillMargins="1" fillOffcut="1" fillingColor="background" normalizeIlluminationColor="0"><posterization-options enabled="0" forceBlackAndWhite="1" level="4" normalizationEnabled="0"/></color-or-grayscale><bw binarizationMethod="otsu" morphologicalSmoothing="1" normalizeIlluminationBW="1" sauvolaCoef="0.34" savitzkyGolaySmoothing="1" thresholdAdj="0" windowSize="200" wolfCoef="0.3" wolfLowerBound="1" wolfUpperBound="254"><color-segmenter-options blueThresholdAdjustment="0" enabled="0" greenThresholdAdjustment="0" noiseReduction="7" redThresholdAdjustment="0"/></bw></color-params><splitting originalBackground="0" splitOutput="0" splittingMode="bw"/><picture-shape-options higherSearchSensitivity="0" pictureShape="free" sensitivity="100"/><distortion-model><top-curve><xspline><point x="334.0224766759178" y="14"/><point x="2204.362853173144" y="0"/><point x="2282.293702193861" y="0"/><point x="2360.224551214579" y="0"/><point x="2438.155400235297" y="0"/><point x="2516.086249256015" y="0"/><point x="2594.017098276733" y="0"/></xspline><polyline>4QKnQwAAYEEYNO5EVVUVQLOkDkUAAAAAmIMTRQAAAAB9YhhFAAAAAGFBHUUAAAAARiAiRQAAAAA=</polyline></top-curve><bottom-curve><xspline><point x="341.4052135553135" y="4960"/><point x="2204.362853173144" y="4960"/><point x="2282.293702193861" y="4960"/><point x="2360.224551214579" y="4960"/><point x="2438.155400235297" y="4960"/><point x="2516.086249256015" y="4960"/><point x="2601.399835156129" y="4960"/></xspline><polyline>3rOqQwAAm0V4W+5EAACbRbOkDkUAAJtFmIMTRQAAm0V9YhhFAACbRRFVHUUAAJtFZpYiRQAAm0U=</polyline></bottom-curve></distortion-model><dewarping-options mode="marginal" postDeskew="1" postDeskewAngle="0"/><processing-params autoZonesFound="0" blackOnWhiteSetManually="0"/></image><source_file mtime="1698783384" size="2890349"/><file mtime="1699405212" size="26562"/><speckles mtime="1699405212" size="690"/><zones/><fill-zones/></output-params></page></output></filters></project>"#;
         let distortion_model = get_distortion_model(CONTENT);
-        match distortion_model {
-            DistortionModel::Marginal(_) => {}
-            _ => assert!(false),
+        distortion_model.validate().unwrap();
+        let DistortionModel::Marginal(model) = distortion_model else {
+            panic!();
+        };
+        // the polyline is traced along the spline, from the same first point
+        let polyline = &model.bottom_curve.polyline;
+        assert_eq!(polyline.len(), 7);
+        assert_eq!(polyline[0], Point::new(Px(341.4052f32.into()), Px(4960f64)));
+        assert!(to_string(&model.bottom_curve).unwrap().contains(
+            "<polyline>3rOqQwAAm0V4W+5EAACbRbOkDkUAAJtFmIMTRQAAm0V9YhhFAACbRRFVHUUAAJtFZpYiRQAAm0U=</polyline>"
+        ));
+    }
+
+    #[test]
+    fn it_evaluates_xsplines() {
+        const CONTENT: &str = r#"<distortion-model><top-curve><xspline><point x="334.0224766759178" y="14"/><point x="2204.362853173144" y="0"/><point x="2282.293702193861" y="0"/><point x="2360.224551214579" y="0"/><point x="2438.155400235297" y="0"/><point x="2516.086249256015" y="0"/><point x="2594.017098276733" y="0"/></xspline><polyline>4QKnQwAAYEEYNO5EVVUVQLOkDkUAAAAAmIMTRQAAAAB9YhhFAAAAAGFBHUUAAAAARiAiRQAAAAA=</polyline></top-curve><bottom-curve><xspline><point x="341.4052135553135" y="4960"/><point x="2204.362853173144" y="4960"/><point x="2282.293702193861" y="4960"/><point x="2360.224551214579" y="4960"/><point x="2438.155400235297" y="4960"/><point x="2516.086249256015" y="4960"/><point x="2601.399835156129" y="4960"/></xspline><polyline>3rOqQwAAm0V4W+5EAACbRbOkDkUAAJtFmIMTRQAAm0V9YhhFAACbRRFVHUUAAJtFZpYiRQAAm0U=</polyline></bottom-curve></distortion-model>"#;
+        let DistortionModel::Marginal(model) = from_str(CONTENT).unwrap() else {
+            panic!();
+        };
+
+        // ScanTailor stores what it samples of the spline
+        for curve in [&model.top_curve, &model.bottom_curve] {
+            let polyline = curve.xspline.to_polyline(1);
+            assert_eq!(polyline.len(), curve.polyline.len());
+            for (a, b) in polyline.iter().zip(&curve.polyline) {
+                assert!(a.distance(b) < Px(1e-3), "{:?} != {:?}", a, b);
+            }
         }
+
+        let xspline = &model.top_curve.xspline;
+        assert_eq!(xspline.point_at(0f64), Some(xspline.point[0]));
+        assert_eq!(xspline.point_at(1f64), Some(xspline.point[6]));
+        let polyline = xspline.to_polyline(8);
+        assert_eq!(polyline.len(), 6 * 8 + 1);
+        assert!(polyline.windows(2).all(|pair| pair[0].x < pair[1].x));
+        assert_eq!(polyline[8], xspline.point_at(1f64 / 6f64).unwrap());
+
+        // a page curling down towards its middle
+        let mut xspline = xspline.clone();
+        xspline.point[3].y = Px(50f64);
+        let model = DistortionModel::Marginal(MarginalDistortionModel {
+            top_curve: XSplineAndPolylineCurve::from(xspline),
+            bottom_curve: model.bottom_curve,
+        });
+        model.validate().unwrap();
+        let DistortionModel::Marginal(written) = from_str(&to_string(&model).unwrap()).unwrap()
+        else {
+            panic!();
+        };
+        let middle = written.top_curve.xspline.point_at(0.5).unwrap();
+        assert!(middle.y > Px(20f64) && middle.y < Px(50f64));
+        assert!((written.top_curve.polyline[3].y - middle.y).abs() < Px(1e-3));
+    }
+
+    #[test]
+    fn it_validates_polylines() {
+        assert!(Polyline::decode(&[0; 12]).is_err());
+
+        for (points, code) in [
+            (vec![Point::new(Px(1f64), Px(2f64))], "polyline_too_short"),
+            (
+                vec![
+                    Point::new(Px(1f64), Px(2f64)),
+                    Point::new(Px(3f64), Px(2f64)),
+                    Point::new(Px(2f64), Px(2f64)),
+                ],
+                "polyline_not_monotonic",
+            ),
+        ] {
+            let curve = PolylineCurve { polyline: points };
+            let errors = curve.validate().unwrap_err();
+            assert_eq!(errors.field_errors()["polyline"][0].code, code);
+        }
+
+        let curve = PolylineCurve {
+            polyline: vec![
+                Point::new(Px(3f64), Px(2f64)),
+                Point::new(Px(1f64), Px(2f64)),
+            ],
+        };
+        curve.validate().unwrap();
     }
 }
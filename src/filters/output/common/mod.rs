@@ -1,4 +1,5 @@
 pub mod color_params;
+pub mod dewarping;
 pub mod dewarping_options;
 pub mod distortion_model;
 pub mod fill_zones;
@@ -9,6 +10,7 @@ pub mod validators;
 pub mod zones;
 
 pub use color_params::{ColorMode, ColorParams};
+pub use dewarping::{CylindricalSurface, DewarpingMesh};
 pub use dewarping_options::{DewarpingMode, DewarpingOptions};
 pub use distortion_model::DistortionModel;
 pub use fill_zones::FillZones;
@@ -1,27 +1,26 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, BoolFromInt};
 use validator::Validate;
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct PictureShapeOptions {
-    #[serde(rename(
-        serialize = "@higherSearchSensitivity",
-        deserialize = "higherSearchSensitivity"
-    ))]
+    #[serde(rename = "@higherSearchSensitivity")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub higher_search_sensitivity: bool,
 
-    #[serde(rename(serialize = "@pictureShape", deserialize = "pictureShape"))]
+    #[serde(rename = "@pictureShape")]
     pub picture_shape: PictureShape,
 
-    #[serde(rename(serialize = "@sensitivity"))]
+    #[serde(rename = "@sensitivity")]
     #[validate(range(min = 0, max = 100))]
     pub sensitivity: u8,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub enum PictureShape {
     Off,
@@ -31,7 +30,7 @@ pub enum PictureShape {
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
 
     use crate::{filters::output::common::picture_shape_options::PictureShape, ProjectXML};
 
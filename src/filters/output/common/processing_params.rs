@@ -1,17 +1,16 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, BoolFromInt};
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct ProcessingParams {
-    #[serde(rename(serialize = "@autoZonesFound", deserialize = "autoZonesFound"))]
+    #[serde(rename = "@autoZonesFound")]
     pub auto_zones_found: u32,
 
-    #[serde(rename(
-        serialize = "@blackOnWhiteSetManually",
-        deserialize = "blackOnWhiteSetManually"
-    ))]
+    #[serde(rename = "@blackOnWhiteSetManually")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub black_on_white_set_manually: bool,
 }
@@ -1,23 +1,26 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, BoolFromInt};
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Splitting {
-    #[serde(rename(serialize = "@originalBackground", deserialize = "originalBackground"))]
+    #[serde(rename = "@originalBackground")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub original_background: bool,
 
-    #[serde(rename(serialize = "@splitOutput", deserialize = "splitOutput"))]
+    #[serde(rename = "@splitOutput")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub split_output: bool,
 
-    #[serde(rename(serialize = "@splittingMode", deserialize = "splittingMode"))]
+    #[serde(rename = "@splittingMode")]
     pub splitting_mode: SplittingMode,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 pub enum SplittingMode {
     #[serde(rename = "bw")]
     BlackAndWhite,
@@ -27,7 +30,7 @@ pub enum SplittingMode {
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
 
     use crate::{filters::output::common::splitting::SplittingMode, ProjectXML};
 
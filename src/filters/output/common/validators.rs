@@ -1,14 +1,19 @@
 use validator::ValidationError;
 
+use crate::validation::invalid;
+
 pub fn validate_despeckle_level(level: f64) -> Result<(), ValidationError> {
-    (level == 0f64 || (level >= 1f64 && level <= 3f64))
+    (level == 0f64 || (1f64..=3f64).contains(&level))
         .then_some(())
-        .ok_or(ValidationError::new("invalid value for despeckle_level"))
+        .ok_or(invalid(
+            "despeckle_level",
+            "invalid value for despeckle_level",
+        ))
 }
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
     use validator::Validate;
 
     use crate::ProjectXML;
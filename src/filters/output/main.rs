@@ -1,24 +1,27 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+use crate::id::PageId;
+
 use super::{
     common::{FillZones, ProcessingParams, Zones},
     output_params::OutputParams,
     params::Params,
 };
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Output {
     #[validate]
     pub page: Vec<Page>,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Page {
-    #[serde(rename(serialize = "@id"))]
-    pub id: u32,
+    #[serde(rename = "@id")]
+    pub id: PageId,
 
     pub zones: Zones,
 
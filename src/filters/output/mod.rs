@@ -3,4 +3,4 @@ mod main;
 pub mod output_params;
 pub mod params;
 
-pub use main::Output;
+pub use main::{Output, Page};
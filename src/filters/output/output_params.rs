@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{
     chrono::{DateTime, Utc},
@@ -5,7 +6,7 @@ use serde_with::{
 };
 use validator::{Validate, ValidationError};
 
-use crate::filters::common::NonNegativeRegionRect;
+use crate::{filters::common::NonNegativeRegionRect, number::QtNumber, validation::invalid};
 
 use super::{
     super::super::common::{IntSize, DPI},
@@ -17,7 +18,7 @@ use super::{
     },
 };
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 #[validate(schema(function = "validate_output_params"))]
 pub struct OutputParams {
@@ -46,86 +47,112 @@ pub struct OutputParams {
     pub fill_zones: FillZones,
 }
 
+impl OutputParams {
+    /// Whether the output is split, with a foreground and a background file.
+    pub fn expects_split_files(&self) -> bool {
+        self.image.splitting.split_output
+    }
+
+    /// Whether ScanTailor writes an automask with these settings.
+    pub fn expects_automask(&self) -> bool {
+        self.image.color_params.color_mode() == ColorMode::Mixed
+    }
+
+    /// Whether ScanTailor writes a speckles file with these settings.
+    pub fn expects_speckles(&self) -> bool {
+        let splitting = &self.image.splitting;
+        self.image.despeckle_level != 0f64
+            && match self.image.color_params.color_mode() {
+                ColorMode::BlackAndWhite => true,
+                ColorMode::ColorOrGrayscale => false,
+                ColorMode::Mixed => {
+                    !splitting.split_output
+                        || splitting.splitting_mode == SplittingMode::BlackAndWhite
+                }
+            }
+    }
+}
+
 fn validate_output_params(params: &OutputParams) -> Result<(), ValidationError> {
     match (
-        params.image.splitting.split_output,
+        params.expects_split_files(),
         &params.foreground_file,
         &params.background_file,
     ) {
         (false, None, None) | (true, Some(_), Some(_)) => {}
         (false, Some(_), _) => {
-            return Err(ValidationError::new(
+            return Err(invalid(
+                "unexpected_foreground_file",
                 "foreground file is present while split output is disabled",
             ));
         }
         (true, None, _) => {
-            return Err(ValidationError::new(
+            return Err(invalid(
+                "missing_foreground_file",
                 "foreground file is absent while split output is enabled",
             ));
         }
         (false, _, Some(_)) => {
-            return Err(ValidationError::new(
+            return Err(invalid(
+                "unexpected_background_file",
                 "background file is present while split output is disabled",
             ));
         }
         (true, _, None) => {
-            return Err(ValidationError::new(
+            return Err(invalid(
+                "missing_background_file",
                 "background file is absent while split output is enabled",
             ));
         }
     };
 
-    match (&params.image.color_params.color_mode, &params.automask) {
-        (ColorMode::BlackAndWhite | ColorMode::ColorOrGrayscale, None)
-        | (ColorMode::Mixed, Some(_)) => {}
-        (ColorMode::Mixed, None) => {
-            return Err(ValidationError::new(
+    match (params.expects_automask(), &params.automask) {
+        (false, None) | (true, Some(_)) => {}
+        (true, None) => {
+            return Err(invalid(
+                "missing_automask",
                 "automask is absent while color mode is mixed",
             ))
         }
-        (ColorMode::BlackAndWhite | ColorMode::ColorOrGrayscale, Some(_)) => {
-            return Err(ValidationError::new(
+        (false, Some(_)) => {
+            return Err(invalid(
+                "unexpected_automask",
                 "automask is present while color mode is not mixed",
             ))
         }
     };
 
-    let despeckle = params.image.despeckle_level != 0f64;
-
-    match (
-        &params.image.color_params.color_mode,
-        &params.image.splitting.split_output,
-        &params.image.splitting.splitting_mode,
-        despeckle,
-        &params.speckles,
-    ) {
-        (ColorMode::BlackAndWhite, _, _, true, Some(_))
-        | (ColorMode::BlackAndWhite, _, _, false, None)
-        | (ColorMode::ColorOrGrayscale, _, _, _, None)
-        | (ColorMode::Mixed, false, _, true, Some(_))
-        | (ColorMode::Mixed, false, _, false, None)
-        | (ColorMode::Mixed, true, SplittingMode::BlackAndWhite, true, Some(_))
-        | (ColorMode::Mixed, true, SplittingMode::Color, _, None) => Ok(()),
-        (_, _, _, _, None) => Err(ValidationError::new("speckles should not be present")),
-        (_, _, _, _, Some(_)) => Err(ValidationError::new("speckles should be present")),
+    match (params.expects_speckles(), &params.speckles) {
+        (false, None) | (true, Some(_)) => Ok(()),
+        (true, None) => Err(invalid("missing_speckles", "speckles should be present")),
+        (false, Some(_)) => Err(invalid(
+            "unexpected_speckles",
+            "speckles should not be present",
+        )),
     }
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 #[validate(schema(function = "validate_image"))]
 pub struct Image {
-    #[serde(rename(serialize = "@blackOnWhite", deserialize = "blackOnWhite"))]
+    #[serde(rename = "@blackOnWhite")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub black_on_white: bool,
 
-    // no idea what this is
-    #[serde(rename(serialize = "@depthPerception", deserialize = "depthPerception"))]
-    pub depth_perception: u32,
+    /// how deep the curvature of the page is taken to be when dewarping
+    #[serde(rename = "@depthPerception")]
+    #[validate(range(min = 1, max = 3))]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub depth_perception: f64,
 
-    #[serde(rename(serialize = "@despeckleLevel", deserialize = "despeckleLevel"))]
+    #[serde(rename = "@despeckleLevel")]
     #[validate(custom = "validate_despeckle_level")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub despeckle_level: f64,
 
     pub size: IntSize,
@@ -154,6 +181,7 @@ pub struct Image {
 
     // avoided using serde(flatten) due to https://github.com/tafia/quick-xml/issues/286#issuecomment-1133762830
     #[serde(rename = "distortion-model", skip_serializing_if = "Option::is_none")]
+    #[validate]
     pub distortion_model: Option<DistortionModel>,
 
     #[serde(rename = "dewarping-options")]
@@ -164,32 +192,45 @@ pub struct Image {
     pub processing_params: ProcessingParams,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct PartialTransform {
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub m11: f64,
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub m12: f64,
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub m21: f64,
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub m22: f64,
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct File {
-    #[serde(rename(serialize = "@mtime"))]
+    #[serde(rename = "@mtime")]
     #[serde_as(as = "TimestampSeconds<i64>")]
+    #[schemars(with = "i64")]
     pub mtime: DateTime<Utc>,
-    #[serde(rename(serialize = "@size"))]
+    #[serde(rename = "@size")]
     pub size: u64,
 }
 
 fn validate_image(image: &Image) -> Result<(), ValidationError> {
     match (&image.distortion_model, &image.dewarping_options.mode) {
-        (None, DewarpingMode::Off | DewarpingMode::Manual)
+        // ScanTailor stores the model edited by hand, if there is one, in manual mode
+        (None, DewarpingMode::Off)
+        | (_, DewarpingMode::Manual)
         | (Some(DistortionModel::Auto(_)), DewarpingMode::Auto)
         | (Some(DistortionModel::Marginal(_)), DewarpingMode::Marginal) => Ok(()),
-        _ => Err(ValidationError::new(
+        _ => Err(invalid(
+            "distortion_model_mismatch",
             "mismatch between dewarping mode and distortion model",
         )),
     }
@@ -197,7 +238,7 @@ fn validate_image(image: &Image) -> Result<(), ValidationError> {
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
     use validator::Validate;
 
     use crate::ProjectXML;
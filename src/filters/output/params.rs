@@ -1,7 +1,10 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, BoolFromInt};
 use validator::{Validate, ValidationError};
 
+use crate::{number::QtNumber, validation::invalid};
+
 use super::{
     super::super::{common::DPI, filters::output::common::Splitting},
     common::{
@@ -11,23 +14,30 @@ use super::{
 };
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 #[validate(schema(function = "validate_params"))]
 pub struct Params {
-    #[serde(rename(serialize = "@blackOnWhite", deserialize = "blackOnWhite"))]
+    #[serde(rename = "@blackOnWhite")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub black_on_white: bool,
 
-    // no idea what this is
-    #[serde(rename(serialize = "@depthPerception", deserialize = "depthPerception"))]
-    pub depth_perception: u32,
+    /// how deep the curvature of the page is taken to be when dewarping
+    #[serde(rename = "@depthPerception")]
+    #[validate(range(min = 1, max = 3))]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub depth_perception: f64,
 
-    #[serde(rename(serialize = "@despeckleLevel", deserialize = "despeckleLevel"))]
+    #[serde(rename = "@despeckleLevel")]
     #[validate(custom = "validate_despeckle_level")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub despeckle_level: f64,
 
     #[serde(rename = "distortion-model", skip_serializing_if = "Option::is_none")]
+    #[validate]
     pub distortion_model: Option<DistortionModel>,
 
     #[serde(rename = "picture-shape-options")]
@@ -48,10 +58,13 @@ pub struct Params {
 
 fn validate_params(params: &Params) -> Result<(), ValidationError> {
     match (&params.distortion_model, &params.dewarping_options.mode) {
-        (None, DewarpingMode::Off | DewarpingMode::Manual)
+        // ScanTailor stores the model edited by hand, if there is one, in manual mode
+        (None, DewarpingMode::Off)
+        | (_, DewarpingMode::Manual)
         | (Some(DistortionModel::Auto(_)), DewarpingMode::Auto)
         | (Some(DistortionModel::Marginal(_)), DewarpingMode::Marginal) => Ok(()),
-        _ => Err(ValidationError::new(
+        _ => Err(invalid(
+            "distortion_model_mismatch",
             "mismatch between dewarping mode and distortion model",
         )),
     }
@@ -59,7 +72,7 @@ fn validate_params(params: &Params) -> Result<(), ValidationError> {
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
     use validator::Validate;
 
     use crate::ProjectXML;
@@ -1,17 +1,19 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, BoolFromInt};
 use validator::Validate;
 
-use crate::common::FloatSize;
+use crate::{common::Size, id::PageId, number::QtNumber, units::Mm};
 
 use super::common::{NonNegativeRegionRect, Rect};
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct PageLayout {
-    #[serde(rename(serialize = "@showMiddleRect", deserialize = "showMiddleRect"))]
+    #[serde(rename = "@showMiddleRect")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub show_middle_rect: bool,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -19,22 +21,23 @@ pub struct PageLayout {
     pub page: Option<Vec<Page>>,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Page {
-    #[serde(rename(serialize = "@id"))]
-    pub id: u32,
+    #[serde(rename = "@id")]
+    pub id: PageId,
 
     #[validate]
     pub params: Params,
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Params {
-    #[serde(rename(serialize = "@autoMargins", deserialize = "autoMargins"))]
+    #[serde(rename = "@autoMargins")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub auto_margins: bool,
 
     #[serde(rename = "hardMarginsMM")]
@@ -49,43 +52,53 @@ pub struct Params {
     pub content_rect: NonNegativeRegionRect,
 
     #[serde(rename = "contentSizeMM")]
-    pub content_size: FloatSize,
+    pub content_size: Size<Mm>,
 
     pub alignment: Alignment,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct HardMarginsMm {
-    #[serde(rename(serialize = "@bottom"))]
-    pub bottom: f64,
-
-    #[serde(rename(serialize = "@left"))]
-    pub left: f64,
-
-    #[serde(rename(serialize = "@right"))]
-    pub right: f64,
-
-    #[serde(rename(serialize = "@top"))]
-    pub top: f64,
+    #[serde(rename = "@bottom")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub bottom: Mm,
+
+    #[serde(rename = "@left")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub left: Mm,
+
+    #[serde(rename = "@right")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub right: Mm,
+
+    #[serde(rename = "@top")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
+    pub top: Mm,
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Alignment {
-    #[serde(rename(serialize = "@hor", deserialize = "hor"))]
+    #[serde(rename = "@hor")]
     pub horizontal: HorizontalAlignment,
 
-    #[serde(rename(serialize = "@null"))]
+    #[serde(rename = "@null")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub null: bool, // decides whether alignment is enabled or not
 
-    #[serde(rename(serialize = "@vert", deserialize = "vert"))]
+    #[serde(rename = "@vert")]
     pub vertical: VerticalAlignment,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub enum HorizontalAlignment {
     Auto,
@@ -95,7 +108,7 @@ pub enum HorizontalAlignment {
     Original,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub enum VerticalAlignment {
     Auto,
@@ -107,7 +120,7 @@ pub enum VerticalAlignment {
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
     use validator::Validate;
 
     use crate::{
@@ -1,34 +1,42 @@
-use serde::{de, Deserialize, Serialize};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use validator::{Validate, ValidationError};
 
-use crate::common::{IntSize, Point};
+use crate::{
+    common::{IntSize, Point},
+    id::ImageId,
+    layout_direction::LayoutDirection,
+    pages::SubPage,
+    units::Px,
+    validation::invalid,
+};
 
 use super::common::{FilledOutline, Rotation};
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct PageSplit {
-    #[serde(rename(serialize = "@defaultLayoutType", deserialize = "defaultLayoutType"))]
+    #[serde(rename = "@defaultLayoutType")]
     pub default_layout_type: DefaultLayoutType,
     #[serde(skip_serializing_if = "Option::is_none")]
     #[validate]
     pub image: Option<Vec<Image>>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub enum DefaultLayoutType {
     AutoDetect,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 #[validate(schema(function = "validate_image"))]
 pub struct Image {
-    #[serde(rename(serialize = "@id"))]
-    pub id: u32,
+    #[serde(rename = "@id")]
+    pub id: ImageId,
 
-    #[serde(rename(serialize = "@layoutType", deserialize = "layoutType"))]
+    #[serde(rename = "@layoutType")]
     pub layout_type: LayoutType,
 
     #[validate]
@@ -44,15 +52,27 @@ fn validate_image(image: &Image) -> Result<(), ValidationError> {
     ) {
         (LayoutType::SingleUncut, Pages::SingleUncut(_), LayoutType::SingleUncut)
         | (LayoutType::SingleCut, Pages::SingleCut(_), LayoutType::SingleCut)
-        | (LayoutType::TwoPages, Pages::TwoPages(_), LayoutType::TwoPages) => Ok(()),
-        _ => Err(ValidationError::new("layout types do not match")),
+        | (LayoutType::TwoPages, Pages::TwoPages(_), LayoutType::TwoPages) => {
+            image.params.pages.validate_cutters()
+        }
+        _ => Err(invalid("layout_type_mismatch", "layout types do not match")),
+    }
+}
+
+impl Image {
+    /// The region of each page cut out of the image, see [`Pages::sub_pages`].
+    pub fn sub_pages(
+        &self,
+        direction: LayoutDirection,
+    ) -> Result<Vec<(SubPage, FilledOutline)>, ValidationError> {
+        self.params.pages.sub_pages(direction)
     }
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Params {
-    #[serde(rename(serialize = "@mode", deserialize = "mode"))]
+    #[serde(rename = "@mode")]
     pub mode: Mode,
 
     #[validate]
@@ -61,30 +81,75 @@ pub struct Params {
     pub dependencies: Dependencies,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum Mode {
     Auto,
     Manual,
 }
 
-#[derive(Serialize, Debug)]
-#[serde(tag = "@type")]
-#[serde(rename_all(serialize = "kebab-case"))]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
+#[serde(tag = "@type", try_from = "PagesFields")]
+#[serde(rename_all = "kebab-case")]
 pub enum Pages {
     SingleUncut(SingleUncutPages),
     SingleCut(SingleCutPages),
     TwoPages(TwoPagesPages),
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+// internally tagged variants are buffered before they are matched, which loses
+// the repeated points of the outline, so the type is matched after reading
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PagesFields {
+    #[serde(rename = "@type")]
+    r#type: LayoutType,
+
+    outline: FilledOutline,
+
+    cutter1: Option<Cutter>,
+
+    cutter2: Option<Cutter>,
+}
+
+impl TryFrom<PagesFields> for Pages {
+    type Error = &'static str;
+
+    fn try_from(fields: PagesFields) -> Result<Self, Self::Error> {
+        let PagesFields {
+            r#type,
+            outline,
+            cutter1,
+            cutter2,
+        } = fields;
+
+        match (r#type, cutter1, cutter2) {
+            (LayoutType::SingleUncut, None, None) => {
+                Ok(Pages::SingleUncut(SingleUncutPages { outline }))
+            }
+            (LayoutType::SingleCut, Some(cutter1), Some(cutter2)) => {
+                Ok(Pages::SingleCut(SingleCutPages {
+                    outline,
+                    cutter1,
+                    cutter2,
+                }))
+            }
+            (LayoutType::TwoPages, Some(cutter1), None) => {
+                Ok(Pages::TwoPages(TwoPagesPages { outline, cutter1 }))
+            }
+            _ => Err("unknown pages structure"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct SingleUncutPages {
     #[validate]
     pub outline: FilledOutline,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct SingleCutPages {
     #[validate]
@@ -95,7 +160,7 @@ pub struct SingleCutPages {
     pub cutter2: Cutter,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct TwoPagesPages {
     #[validate]
@@ -104,50 +169,105 @@ pub struct TwoPagesPages {
     pub cutter1: Cutter,
 }
 
-impl<'de> Deserialize<'de> for Pages {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        #[derive(Deserialize)]
-        #[serde(deny_unknown_fields)]
-        struct S {
-            #[serde(rename(serialize = "@type", deserialize = "type"))]
-            pub r#type: LayoutType,
-
-            pub outline: FilledOutline,
-
-            pub cutter1: Option<Cutter>,
-
-            pub cutter2: Option<Cutter>,
-        }
-
-        let S {
-            r#type,
-            outline,
-            cutter1,
-            cutter2,
-        } = S::deserialize(deserializer)?;
+impl Pages {
+    /// The region of each page cut out of the outline, in reading order: the left page
+    /// comes first from left to right, the right one from right to left.
+    ///
+    /// Cutters are taken as the whole lines they lie on, as ScanTailor does, and the
+    /// outline is expected to be convex, as the image rectangle is.
+    pub fn sub_pages(
+        &self,
+        direction: LayoutDirection,
+    ) -> Result<Vec<(SubPage, FilledOutline)>, ValidationError> {
+        self.validate_cutters()?;
+        Ok(match self {
+            Pages::SingleUncut(pages) => vec![(SubPage::Single, pages.outline.clone())],
+            Pages::SingleCut(pages) => {
+                // the page is what lies between both cutters, which validation keeps
+                // off each other's line
+                let (cutter1, cutter2) = (&pages.cutter1, &pages.cutter2);
+                let (sign1, sign2) = (
+                    cutter1.side(&cutter2.middle()).signum(),
+                    cutter2.side(&cutter1.middle()).signum(),
+                );
+                let page = clip(&pages.outline, |point| cutter1.side(point) * sign1);
+                let page = clip(&page, |point| cutter2.side(point) * sign2);
+                vec![(SubPage::Single, page)]
+            }
+            Pages::TwoPages(pages) => {
+                let cutter = &pages.cutter1;
+                let left = clip(&pages.outline, |point| cutter.side(point));
+                let right = clip(&pages.outline, |point| -cutter.side(point));
+                match direction {
+                    LayoutDirection::LTR => {
+                        vec![(SubPage::Left, left), (SubPage::Right, right)]
+                    }
+                    LayoutDirection::RTL => {
+                        vec![(SubPage::Right, right), (SubPage::Left, left)]
+                    }
+                }
+            }
+        })
+    }
 
-        match (r#type, cutter1, cutter2) {
-            (LayoutType::SingleUncut, None, None) => {
-                Ok(Pages::SingleUncut(SingleUncutPages { outline }))
+    /// Checks that every cutter goes through or touches the outline, and that cutters
+    /// neither cross within it nor lie on the same line.
+    fn validate_cutters(&self) -> Result<(), ValidationError> {
+        let (outline, cutters) = match self {
+            Pages::SingleUncut(_) => return Ok(()),
+            Pages::SingleCut(pages) => (&pages.outline, vec![&pages.cutter1, &pages.cutter2]),
+            Pages::TwoPages(pages) => (&pages.outline, vec![&pages.cutter1]),
+        };
+        for cutter in cutters.iter() {
+            let sides = outline
+                .polygon()
+                .iter()
+                .map(|point| cutter.side(point))
+                .collect::<Vec<_>>();
+            if cutter.length() == Px(0f64)
+                || sides.iter().all(|side| *side > 0f64)
+                || sides.iter().all(|side| *side < 0f64)
+            {
+                return Err(invalid(
+                    "cutter_outside_outline",
+                    "cutter does not go through the outline",
+                ));
             }
-            (LayoutType::SingleCut, Some(cutter1), Some(cutter2)) => {
-                Ok(Pages::SingleCut(SingleCutPages {
-                    outline,
-                    cutter1,
-                    cutter2,
-                }))
+        }
+        if let [cutter1, cutter2] = cutters[..] {
+            if cutter1.side(&cutter2.middle()) == 0f64 || cutter2.side(&cutter1.middle()) == 0f64 {
+                return Err(invalid("collinear_cutters", "cutters lie on the same line"));
             }
-            (LayoutType::TwoPages, Some(cutter1), None) => {
-                Ok(Pages::TwoPages(TwoPagesPages { outline, cutter1 }))
+            if let Some(point) = cutter1.crossing(cutter2) {
+                if outline.contains(&point) {
+                    return Err(invalid(
+                        "crossing_cutters",
+                        "cutters cross within the outline",
+                    ));
+                }
             }
-            _ => Err(de::Error::custom("unknown pages structure")),
         }
+        Ok(())
     }
 }
 
+/// Keeps the part of a convex outline where `side` is not negative.
+fn clip(outline: &FilledOutline, side: impl Fn(&Point) -> f64) -> FilledOutline {
+    let polygon = outline.polygon();
+    let mut clipped = vec![];
+    for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        let (side_a, side_b) = (side(a), side(b));
+        if side_a >= 0f64 {
+            clipped.push(*a);
+        }
+        if (side_a < 0f64 && side_b > 0f64) || (side_a > 0f64 && side_b < 0f64) {
+            let t = side_a / (side_a - side_b);
+            clipped.push(Point::new(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t));
+        }
+    }
+    FilledOutline::from_polygon(clipped)
+}
+
 impl Validate for Pages {
     fn validate(&self) -> Result<(), validator::ValidationErrors> {
         match self {
@@ -158,14 +278,64 @@ impl Validate for Pages {
     }
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Clone, Copy, PartialEq, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Cutter {
-    p1: Point,
-    p2: Point,
+    pub p1: Point,
+    pub p2: Point,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+impl Cutter {
+    pub fn new(p1: Point, p2: Point) -> Self {
+        Cutter { p1, p2 }
+    }
+
+    pub fn length(&self) -> Px {
+        self.p1.distance(&self.p2)
+    }
+
+    pub fn translate(&self, dx: Px, dy: Px) -> Self {
+        Cutter::new(self.p1.translate(dx, dy), self.p2.translate(dx, dy))
+    }
+
+    fn middle(&self) -> Point {
+        Point::new(
+            (self.p1.x + self.p2.x) / 2f64,
+            (self.p1.y + self.p2.y) / 2f64,
+        )
+    }
+
+    /// Scales the cutter away from the origin.
+    pub fn scale(&self, sx: f64, sy: f64) -> Self {
+        Cutter::new(self.p1.scale(sx, sy), self.p2.scale(sx, sy))
+    }
+
+    /// Which side of the line of the cutter the point is on: positive on the left,
+    /// negative on the right and zero on the line, whichever way the cutter was drawn.
+    pub fn side(&self, point: &Point) -> f64 {
+        let (top, bottom) = if self.p1.y <= self.p2.y {
+            (&self.p1, &self.p2)
+        } else {
+            (&self.p2, &self.p1)
+        };
+        (bottom.x - top.x).0 * (point.y - top.y).0 - (bottom.y - top.y).0 * (point.x - top.x).0
+    }
+
+    /// Where the lines of both cutters cross, unless they are parallel.
+    pub fn crossing(&self, other: &Cutter) -> Option<Point> {
+        let (dx1, dy1) = (self.p2.x - self.p1.x, self.p2.y - self.p1.y);
+        let (dx2, dy2) = (other.p2.x - other.p1.x, other.p2.y - other.p1.y);
+        let denominator = dx1.0 * dy2.0 - dy1.0 * dx2.0;
+        if denominator == 0f64 {
+            return None;
+        }
+        let t =
+            ((other.p1.x - self.p1.x).0 * dy2.0 - (other.p1.y - self.p1.y).0 * dx2.0) / denominator;
+        Some(Point::new(self.p1.x + dx1 * t, self.p1.y + dy1 * t))
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Dependencies {
     pub rotation: Rotation,
@@ -174,7 +344,7 @@ pub struct Dependencies {
     pub layout_type: LayoutType,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "kebab-case")]
 pub enum LayoutType {
@@ -186,15 +356,23 @@ pub enum LayoutType {
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
     use validator::Validate;
 
     use crate::{
-        filters::page_split::{Image, LayoutType, Mode, Pages},
+        common::Point,
+        filters::{
+            common::{FilledOutline, Rect},
+            page_split::{Cutter, Image, LayoutType, Mode, Pages, TwoPagesPages},
+        },
+        layout_direction::LayoutDirection,
+        pages::SubPage,
+        project::test::ORIGINAL_CONTENT,
+        units::Px,
         ProjectXML,
     };
 
-    fn get_image<'a>(content: &str, validate: bool) -> Image {
+    fn get_image(content: &str, validate: bool) -> Image {
         let project: ProjectXML = from_str(content).unwrap();
         if validate {
             project.validate().unwrap();
@@ -205,6 +383,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn it_deserializes_and_validates_single_uncut_pages() {
         const CONTENT: &str = r#"<project layoutDirection="LTR" outputDirectory="/home/ramzi/src/bill-organizer/out" version="3"><directories><directory id="1" path="/home/ramzi/src/bill-organizer"/></directories><files><file dirId="1" id="2" name="in.jpg"/></files><images><image fileId="2" fileImage="0" id="3" subPages="1"><size height="4961" width="7016"/><dpi horizontal="600" vertical="600"/></image></images><pages><page id="4" imageId="3" selected="selected" subPage="single"/></pages><file-name-disambiguation><mapping file="2" label="0"/></file-name-disambiguation><filters><fix-orientation><image-settings><page id="4"><image-params blackOnWhite="1" bwThreshold="193"/></page></image-settings></fix-orientation><page-split defaultLayoutType="auto-detect"><image id="3" layoutType="single-uncut"><params mode="auto"><pages type="single-uncut"><outline><point x="0" y="0"/><point x="7016" y="0"/><point x="7016" y="4961"/><point x="0" y="4961"/><point x="0" y="0"/></outline></pages><dependencies><rotation degrees="0"/><size height="4961" width="7016"/><layoutType>single-uncut</layoutType></dependencies></params></image></page-split><deskew><page id="4"><params angle="0" mode="auto"><dependencies><rotation degrees="0"/><page-outline/></dependencies></params></page><image-settings/></deskew><select-content pageDetectionTolerance="0.1"><page id="4"><params contentDetectionMode="auto" fineTuneCorners="0" pageDetectionMode="disabled"><content-rect height="0" width="0" x="0" y="0"/><page-rect height="7015.733999999999" width="4960.62" x="0" y="0"/><content-size-mm height="-1" width="-1"/><dependencies><rotated-page-outline/><params contentDetectionMode="auto" fineTuneCorners="0" pageDetectionMode="disabled"/></dependencies></params></page></select-content><page-layout showMiddleRect="1"><page id="4"><params autoMargins="0"><hardMarginsMM bottom="5" left="10" right="10" top="5"/><pageRect height="0" width="0" x="0" y="0"/><contentRect height="0" width="0" x="0" y="0"/><contentSizeMM height="-1" width="-1"/><alignment hor="center" null="0" vert="center"/></params></page></page-layout><output><page id="4"><zones/><fill-zones/><params blackOnWhite="1" depthPerception="2" despeckleLevel="1"><picture-shape-options higherSearchSensitivity="0" pictureShape="free" sensitivity="100"/><dewarping-options mode="off" postDeskew="1" postDeskewAngle="0"/><dpi horizontal="600" vertical="600"/><color-params colorMode="bw"><color-or-grayscale fillMargins="1" fillOffcut="1" fillingColor="background" normalizeIlluminationColor="0"><posterization-options enabled="0" forceBlackAndWhite="1" level="4" normalizationEnabled="0"/></color-or-grayscale><bw binarizationMethod="otsu" morphologicalSmoothing="1" normalizeIlluminationBW="1" sauvolaCoef="0.34" savitzkyGolaySmoothing="1" thresholdAdj="0" windowSize="200" wolfCoef="0.3" wolfLowerBound="1" wolfUpperBound="254"><color-segmenter-options blueThresholdAdjustment="0" enabled="0" greenThresholdAdjustment="0" noiseReduction="7" redThresholdAdjustment="0"/></bw></color-params><splitting originalBackground="0" splitOutput="0" splittingMode="bw"/></params><processing-params autoZonesFound="0" blackOnWhiteSetManually="0"/></page></output></filters></project>"#;
         let image = get_image(CONTENT, true);
@@ -218,6 +397,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn it_deserializes_and_validates_single_cut_pages() {
         const CONTENT: &str = r#"<project layoutDirection="LTR" outputDirectory="/home/ramzi/src/bill-organizer/out" version="3"><directories><directory id="1" path="/home/ramzi/src/bill-organizer"/></directories><files><file dirId="1" id="2" name="in.jpg"/></files><images><image fileId="2" fileImage="0" id="3" subPages="1"><size height="4961" width="7016"/><dpi horizontal="600" vertical="600"/></image></images><pages><page id="4" imageId="3" selected="selected" subPage="single"/></pages><file-name-disambiguation><mapping file="2" label="0"/></file-name-disambiguation><filters><fix-orientation><image-settings><page id="4"><image-params blackOnWhite="1" bwThreshold="193"/></page></image-settings></fix-orientation><page-split defaultLayoutType="auto-detect"><image id="3" layoutType="single-cut"><params mode="auto"><pages type="single-cut"><outline><point x="0" y="0"/><point x="7016" y="0"/><point x="7016" y="4961"/><point x="0" y="4961"/><point x="0" y="0"/></outline><cutter1><p1 x="1361.028490094375" y="0"/><p2 x="1382.679436866696" y="4962"/></cutter1><cutter2><p1 x="2817.82175895945" y="4962"/><p2 x="2861.124476937195" y="0"/></cutter2></pages><dependencies><rotation degrees="0"/><size height="4961" width="7016"/><layoutType>single-cut</layoutType></dependencies></params></image></page-split><deskew><page id="4"><params angle="0" mode="auto"><dependencies><rotation degrees="0"/><page-outline/></dependencies></params></page><image-settings/></deskew><select-content pageDetectionTolerance="0.1"><page id="4"><params contentDetectionMode="auto" fineTuneCorners="0" pageDetectionMode="disabled"><content-rect height="0" width="0" x="0" y="0"/><page-rect height="7015.733999999999" width="4960.62" x="0" y="0"/><content-size-mm height="-1" width="-1"/><dependencies><rotated-page-outline/><params contentDetectionMode="auto" fineTuneCorners="0" pageDetectionMode="disabled"/></dependencies></params></page></select-content><page-layout showMiddleRect="1"><page id="4"><params autoMargins="0"><hardMarginsMM bottom="5" left="10" right="10" top="5"/><pageRect height="0" width="0" x="0" y="0"/><contentRect height="0" width="0" x="0" y="0"/><contentSizeMM height="-1" width="-1"/><alignment hor="center" null="0" vert="center"/></params></page></page-layout><output><page id="4"><zones/><fill-zones/><params blackOnWhite="1" depthPerception="2" despeckleLevel="1"><picture-shape-options higherSearchSensitivity="0" pictureShape="free" sensitivity="100"/><dewarping-options mode="off" postDeskew="1" postDeskewAngle="0"/><dpi horizontal="600" vertical="600"/><color-params colorMode="bw"><color-or-grayscale fillMargins="1" fillOffcut="1" fillingColor="background" normalizeIlluminationColor="0"><posterization-options enabled="0" forceBlackAndWhite="1" level="4" normalizationEnabled="0"/></color-or-grayscale><bw binarizationMethod="otsu" morphologicalSmoothing="1" normalizeIlluminationBW="1" sauvolaCoef="0.34" savitzkyGolaySmoothing="1" thresholdAdj="0" windowSize="200" wolfCoef="0.3" wolfLowerBound="1" wolfUpperBound="254"><color-segmenter-options blueThresholdAdjustment="0" enabled="0" greenThresholdAdjustment="0" noiseReduction="7" redThresholdAdjustment="0"/></bw></color-params><splitting originalBackground="0" splitOutput="0" splittingMode="bw"/></params><processing-params autoZonesFound="0" blackOnWhiteSetManually="0"/></page></output></filters></project>"#;
         let image = get_image(CONTENT, true);
@@ -231,6 +411,7 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants)]
     fn it_deserializes_and_validates_two_pages_pages() {
         const CONTENT: &str = r#"<project layoutDirection="LTR" outputDirectory="/home/ramzi/src/bill-organizer/out" version="3"><directories><directory id="1" path="/home/ramzi/src/bill-organizer"/></directories><files><file dirId="1" id="2" name="in.jpg"/></files><images><image fileId="2" fileImage="0" id="3" subPages="2"><size height="4961" width="7016"/><dpi horizontal="600" vertical="600"/></image></images><pages><page id="4" imageId="3" selected="selected" subPage="left"/><page id="5" imageId="3" subPage="right"/></pages><file-name-disambiguation><mapping file="2" label="0"/></file-name-disambiguation><filters><fix-orientation><image-settings/></fix-orientation><page-split defaultLayoutType="auto-detect"><image id="3" layoutType="two-pages"><params mode="auto"><pages type="two-pages"><outline><point x="0" y="0"/><point x="7016" y="0"/><point x="7016" y="4961"/><point x="0" y="4961"/><point x="0" y="0"/></outline><cutter1><p1 x="2817.82175895945" y="4962"/><p2 x="2861.124476937195" y="0"/></cutter1></pages><dependencies><rotation degrees="0"/><size height="4961" width="7016"/><layoutType>two-pages</layoutType></dependencies></params></image></page-split><deskew><image-settings/></deskew><select-content pageDetectionTolerance="0.1"/><page-layout showMiddleRect="1"/><output><page id="4"><zones/><fill-zones/><params blackOnWhite="1" depthPerception="2" despeckleLevel="1"><picture-shape-options higherSearchSensitivity="0" pictureShape="free" sensitivity="100"/><dewarping-options mode="off" postDeskew="1" postDeskewAngle="0"/><dpi horizontal="600" vertical="600"/><color-params colorMode="bw"><color-or-grayscale fillMargins="1" fillOffcut="1" fillingColor="background" normalizeIlluminationColor="0"><posterization-options enabled="0" forceBlackAndWhite="1" level="4" normalizationEnabled="0"/></color-or-grayscale><bw binarizationMethod="otsu" morphologicalSmoothing="1" normalizeIlluminationBW="1" sauvolaCoef="0.34" savitzkyGolaySmoothing="1" thresholdAdj="0" windowSize="200" wolfCoef="0.3" wolfLowerBound="1" wolfUpperBound="254"><color-segmenter-options blueThresholdAdjustment="0" enabled="0" greenThresholdAdjustment="0" noiseReduction="7" redThresholdAdjustment="0"/></bw></color-params><splitting originalBackground="0" splitOutput="0" splittingMode="bw"/></params><processing-params autoZonesFound="0" blackOnWhiteSetManually="0"/></page><page id="5"><zones/><fill-zones/><params blackOnWhite="1" depthPerception="2" despeckleLevel="1"><picture-shape-options higherSearchSensitivity="0" pictureShape="free" sensitivity="100"/><dewarping-options mode="off" postDeskew="1" postDeskewAngle="0"/><dpi horizontal="600" vertical="600"/><color-params colorMode="bw"><color-or-grayscale fillMargins="1" fillOffcut="1" fillingColor="background" normalizeIlluminationColor="0"><posterization-options enabled="0" forceBlackAndWhite="1" level="4" normalizationEnabled="0"/></color-or-grayscale><bw binarizationMethod="otsu" morphologicalSmoothing="1" normalizeIlluminationBW="1" sauvolaCoef="0.34" savitzkyGolaySmoothing="1" thresholdAdj="0" windowSize="200" wolfCoef="0.3" wolfLowerBound="1" wolfUpperBound="254"><color-segmenter-options blueThresholdAdjustment="0" enabled="0" greenThresholdAdjustment="0" noiseReduction="7" redThresholdAdjustment="0"/></bw></color-params><splitting originalBackground="0" splitOutput="0" splittingMode="bw"/></params><processing-params autoZonesFound="0" blackOnWhiteSetManually="0"/></page></output></filters></project>"#;
         let image = get_image(CONTENT, true);
@@ -260,4 +441,102 @@ mod test {
             assert_eq!(image.params.mode, expected_mode);
         }
     }
+
+    #[test]
+    fn it_cuts_pages_out_of_the_outline() {
+        // the page deskew was given is what lies between the cutters
+        let project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        let image = &project.filters.page_split.image.as_ref().unwrap()[0];
+        let sub_pages = image.sub_pages(LayoutDirection::LTR).unwrap();
+        assert_eq!(sub_pages.len(), 1);
+        assert_eq!(sub_pages[0].0, SubPage::Single);
+        assert_eq!(sub_pages[0].1.polygon().len(), 4);
+        let page = sub_pages[0].1.bounding_box().unwrap();
+        assert!((page.x.0 - 1142.578008915305).abs() < 1e-9);
+        assert!((page.right().0 - 4959.99008).abs() < 1e-9);
+        assert_eq!(page.height, Px(7016f64));
+
+        let cutter = Cutter::new(
+            Point::new(Px(3000f64), Px(1000f64)),
+            Point::new(Px(3100f64), Px(0f64)),
+        );
+        let pages = Pages::TwoPages(TwoPagesPages {
+            outline: FilledOutline::from(&Rect::new(Px(0f64), Px(0f64), Px(7016f64), Px(1000f64))),
+            cutter1: cutter,
+        });
+        let sub_pages = pages.sub_pages(LayoutDirection::RTL).unwrap();
+        assert_eq!(
+            sub_pages
+                .iter()
+                .map(|(sub_page, _)| *sub_page)
+                .collect::<Vec<_>>(),
+            vec![SubPage::Right, SubPage::Left]
+        );
+        let (right, left) = (&sub_pages[0].1, &sub_pages[1].1);
+        assert!(left.contains(&Point::new(Px(3040f64), Px(500f64))));
+        assert!(right.contains(&Point::new(Px(3060f64), Px(500f64))));
+        assert!((left.area() - 3050f64 * 1000f64).abs() < 1e-6);
+        assert!((left.area() + right.area() - 7016f64 * 1000f64).abs() < 1e-6);
+        assert_eq!(
+            pages.sub_pages(LayoutDirection::LTR).unwrap()[0].1,
+            left.clone()
+        );
+    }
+
+    #[test]
+    fn it_validates_cutters() {
+        let outside = ORIGINAL_CONTENT.replacen(
+            r#"<p1 x="1142.578008915305" y="66.34323922734029"/><p2 x="1142.578008915305" y="6964.399702823181"/>"#,
+            r#"<p1 x="5000" y="0"/><p2 x="5000" y="7016"/>"#,
+            1,
+        );
+        let crossing = ORIGINAL_CONTENT.replacen(
+            r#"<p1 x="1142.578008915305" y="66.34323922734029"/><p2 x="1142.578008915305" y="6964.399702823181"/>"#,
+            r#"<p1 x="4000" y="0"/><p2 x="5500" y="7016"/>"#,
+            1,
+        );
+        for (content, code) in [
+            (outside, "cutter_outside_outline"),
+            (crossing, "crossing_cutters"),
+        ] {
+            let project: ProjectXML = from_str(&content).unwrap();
+            let image = &project.filters.page_split.image.as_ref().unwrap()[0];
+            assert_eq!(
+                image.sub_pages(LayoutDirection::LTR).unwrap_err().code,
+                code
+            );
+            assert!(project.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn it_cuts_along_the_outline_edge() {
+        let content = ORIGINAL_CONTENT.replacen(
+            r#"<p1 x="1142.578008915305" y="66.34323922734029"/><p2 x="1142.578008915305" y="6964.399702823181"/>"#,
+            r#"<p1 x="0" y="0"/><p2 x="0" y="7016"/>"#,
+            1,
+        );
+        let project: ProjectXML = from_str(&content).unwrap();
+        let image = &project.filters.page_split.image.as_ref().unwrap()[0];
+        let sub_pages = image.sub_pages(LayoutDirection::LTR).unwrap();
+        let bounds = sub_pages[0].1.bounding_box().unwrap();
+        assert_eq!((bounds.x, bounds.width), (Px(0f64), Px(4959.99008)));
+        assert!(project.validate().is_ok());
+    }
+
+    #[test]
+    fn it_rejects_collinear_cutters() {
+        let content = ORIGINAL_CONTENT.replacen(
+            r#"<p1 x="1142.578008915305" y="66.34323922734029"/><p2 x="1142.578008915305" y="6964.399702823181"/>"#,
+            r#"<p1 x="4959.99008" y="0"/><p2 x="4959.99008" y="7015.985967999999"/>"#,
+            1,
+        );
+        let project: ProjectXML = from_str(&content).unwrap();
+        let image = &project.filters.page_split.image.as_ref().unwrap()[0];
+        assert_eq!(
+            image.sub_pages(LayoutDirection::LTR).unwrap_err().code,
+            "collinear_cutters"
+        );
+        assert!(project.validate().is_err());
+    }
 }
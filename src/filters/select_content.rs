@@ -1,19 +1,19 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::{serde_as, BoolFromInt};
 use validator::{Validate, ValidationError};
 
-use super::{
-    super::common::FloatSize,
-    common::{NonNegativeRegionRect, Outline, Rect},
-};
+use crate::{common::Size, id::PageId, number::QtNumber, units::Mm, validation::invalid};
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+use super::common::{NonNegativeRegionRect, Outline, Rect};
+
+#[serde_as]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct SelectContent {
-    #[serde(rename(
-        serialize = "@pageDetectionTolerance",
-        deserialize = "pageDetectionTolerance"
-    ))]
+    #[serde(rename = "@pageDetectionTolerance")]
+    #[serde_as(as = "QtNumber")]
+    #[schemars(with = "f64")]
     pub page_detection_tolerance: f64,
 
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -21,32 +21,30 @@ pub struct SelectContent {
     pub page: Option<Vec<Page>>,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Page {
-    #[serde(rename(serialize = "@id"))]
-    pub id: u32,
+    #[serde(rename = "@id")]
+    pub id: PageId,
 
     #[validate]
     pub params: PageParams,
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 #[validate(schema(function = "validate_page_params"))]
 pub struct PageParams {
-    #[serde(rename(
-        serialize = "@contentDetectionMode",
-        deserialize = "contentDetectionMode"
-    ))]
+    #[serde(rename = "@contentDetectionMode")]
     pub content_detection_mode: ContentDetectionMode,
 
-    #[serde(rename(serialize = "@fineTuneCorners", deserialize = "fineTuneCorners"))]
+    #[serde(rename = "@fineTuneCorners")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub fine_tune_corners: bool,
 
-    #[serde(rename(serialize = "@pageDetectionMode", deserialize = "pageDetectionMode"))]
+    #[serde(rename = "@pageDetectionMode")]
     pub page_detection_mode: PageDetectionMode,
 
     #[serde(rename = "content-rect")]
@@ -58,7 +56,7 @@ pub struct PageParams {
     pub page_rect: Rect,
 
     #[serde(rename = "content-size-mm")]
-    pub content_size_mm: FloatSize,
+    pub content_size_mm: Size<Mm>,
 
     #[validate]
     pub dependencies: Dependencies,
@@ -67,14 +65,20 @@ pub struct PageParams {
 fn validate_page_params(params: &PageParams) -> Result<(), ValidationError> {
     (params.page_detection_mode == params.dependencies.params.page_detection_mode)
         .then_some(())
-        .ok_or(ValidationError::new("page detection modes do not match"))?;
+        .ok_or(invalid(
+            "page_detection_mode_mismatch",
+            "page detection modes do not match",
+        ))?;
 
     (params.content_detection_mode == params.dependencies.params.content_detection_mode)
         .then_some(())
-        .ok_or(ValidationError::new("content detection modes do not match"))
+        .ok_or(invalid(
+            "content_detection_mode_mismatch",
+            "content detection modes do not match",
+        ))
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub enum PageDetectionMode {
     Disabled,
@@ -82,7 +86,7 @@ pub enum PageDetectionMode {
     Manual,
 }
 
-#[derive(Serialize, Deserialize, Eq, PartialEq, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Eq, PartialEq, Debug)]
 #[serde(rename_all = "kebab-case")]
 pub enum ContentDetectionMode {
     Disabled,
@@ -90,7 +94,7 @@ pub enum ContentDetectionMode {
     Manual,
 }
 
-#[derive(Serialize, Deserialize, Validate, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Validate, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Dependencies {
     #[serde(rename = "rotated-page-outline")]
@@ -100,24 +104,22 @@ pub struct Dependencies {
 }
 
 #[serde_as]
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct DependenciesParams {
-    #[serde(rename(
-        serialize = "@contentDetectionMode",
-        deserialize = "contentDetectionMode"
-    ))]
+    #[serde(rename = "@contentDetectionMode")]
     pub content_detection_mode: ContentDetectionMode,
-    #[serde(rename(serialize = "@fineTuneCorners", deserialize = "fineTuneCorners"))]
+    #[serde(rename = "@fineTuneCorners")]
     #[serde_as(as = "BoolFromInt")]
+    #[schemars(with = "u8", range(max = 1))]
     pub fine_tune_corners: bool,
-    #[serde(rename(serialize = "@pageDetectionMode", deserialize = "pageDetectionMode"))]
+    #[serde(rename = "@pageDetectionMode")]
     pub page_detection_mode: PageDetectionMode,
 }
 
 #[cfg(test)]
 mod test {
-    use serde_xml_rs::from_str;
+    use quick_xml::de::from_str;
     use validator::Validate;
 
     use crate::{
@@ -0,0 +1,77 @@
+use std::fmt::{self, Display};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// ScanTailor numbers directories, files, images and pages from one counter, so ids of
+// different kinds can look alike; these keep an image id from being used as a page id.
+
+/// Id of a [`Directory`](crate::directories::Directory).
+#[derive(
+    Serialize, Deserialize, JsonSchema, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug,
+)]
+#[serde(transparent)]
+pub struct DirectoryId(pub u32);
+
+/// Id of a [`File`](crate::files::File).
+#[derive(
+    Serialize, Deserialize, JsonSchema, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug,
+)]
+#[serde(transparent)]
+pub struct FileId(pub u32);
+
+/// Id of an [`Image`](crate::images::Image), used by the fix orientation and page split filters.
+#[derive(
+    Serialize, Deserialize, JsonSchema, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug,
+)]
+#[serde(transparent)]
+pub struct ImageId(pub u32);
+
+/// Id of a [`Page`](crate::pages::Page), used by the filters from deskew on.
+#[derive(
+    Serialize, Deserialize, JsonSchema, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug,
+)]
+#[serde(transparent)]
+pub struct PageId(pub u32);
+
+impl Display for DirectoryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Display for FileId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Display for ImageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Display for PageId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::de::from_str;
+
+    use crate::{directories::Directory, files::File};
+
+    use super::DirectoryId;
+
+    #[test]
+    fn it_rejects_negative_directory_ids() {
+        let directory: Directory = from_str(r#"<directory id="1" path="/scans"/>"#).unwrap();
+        assert_eq!(directory.id, DirectoryId(1));
+
+        assert!(from_str::<Directory>(r#"<directory id="-1" path="/scans"/>"#).is_err());
+        assert!(from_str::<File>(r#"<file dirId="-1" id="2" name="in.jpg"/>"#).is_err());
+    }
+}
@@ -1,26 +1,30 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use super::common::{IntSize, DPI};
+use super::{
+    common::{IntSize, DPI},
+    id::{FileId, ImageId},
+};
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Images {
     pub image: Vec<Image>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Image {
-    #[serde(rename(serialize = "@fileId", deserialize = "fileId"))]
-    pub file_id: u32,
+    #[serde(rename = "@fileId")]
+    pub file_id: FileId,
 
-    #[serde(rename(serialize = "@fileImage", deserialize = "fileImage"))]
+    #[serde(rename = "@fileImage")]
     pub file_image: u32,
 
-    #[serde(rename(serialize = "@id"))]
-    pub id: u32,
+    #[serde(rename = "@id")]
+    pub id: ImageId,
 
-    #[serde(rename(serialize = "@subPages", deserialize = "subPages"))]
+    #[serde(rename = "@subPages")]
     pub sub_pages: u32,
 
     pub size: IntSize,
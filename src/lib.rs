@@ -1,11 +1,29 @@
+pub mod canonical;
 pub mod common;
+pub mod consistency;
 pub mod directories;
+pub mod error;
 pub mod file_name_disambiguation;
 pub mod files;
 pub mod filters;
+pub mod id;
 pub mod images;
 pub mod layout_direction;
+pub mod lint;
+pub mod load;
+pub mod mapping;
+pub mod migration;
+pub mod normalize;
+pub mod number;
 pub mod pages;
+pub mod picture_zones;
 mod project;
+pub mod report;
+pub mod schema;
+pub mod stream;
+pub mod transform;
+pub mod units;
+mod validation;
+mod xml;
 
 pub use project::ProjectXML;
@@ -0,0 +1,595 @@
+use std::{collections::HashMap, str::FromStr};
+
+use anyhow::{anyhow, Error};
+
+use crate::{
+    common::DPI,
+    filters::output::common::{ColorMode, ColorParams},
+    number::format_double,
+    report::{Problem, Report, Severity},
+    units::Px,
+};
+
+use super::ProjectXML;
+
+/// Something suspicious in a project that is nevertheless valid, and
+/// usually the sign of a scanning mistake.
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Debug)]
+pub enum Lint {
+    /// an image was scanned at another DPI than most
+    MixedDpi,
+    /// a deskew angle is close to the ±45° limit
+    DeskewNearLimit,
+    /// the content covers less than 5% of its page
+    SmallContent,
+    /// the Wolf binarization lower bound is not below its upper bound
+    WolfBoundsInverted,
+    /// a page has another color mode than the pages of the same bill around it
+    InconsistentColorMode,
+    /// the page detection tolerance is outside the usual range
+    PageDetectionTolerance,
+}
+
+/// Deskew angles at least this far from 0 are suspicious.
+const DESKEW_NEAR_LIMIT: f64 = 40f64;
+
+/// Content covering less of its page than this is suspicious.
+const SMALL_CONTENT: f64 = 0.05;
+
+/// ScanTailor detects pages with a tolerance of 0.1 by default.
+const PAGE_DETECTION_TOLERANCE: (f64, f64) = (0.01, 0.5);
+
+impl Lint {
+    pub const ALL: [Lint; 6] = [
+        Lint::MixedDpi,
+        Lint::DeskewNearLimit,
+        Lint::SmallContent,
+        Lint::WolfBoundsInverted,
+        Lint::InconsistentColorMode,
+        Lint::PageDetectionTolerance,
+    ];
+
+    /// The stable identifier of the lint, used as the code of its problems.
+    pub fn id(&self) -> &'static str {
+        match self {
+            Lint::MixedDpi => "mixed_dpi",
+            Lint::DeskewNearLimit => "deskew_near_limit",
+            Lint::SmallContent => "small_content",
+            Lint::WolfBoundsInverted => "wolf_bounds_inverted",
+            Lint::InconsistentColorMode => "inconsistent_color_mode",
+            Lint::PageDetectionTolerance => "page_detection_tolerance",
+        }
+    }
+
+    pub fn default_level(&self) -> Level {
+        Level::Warn
+    }
+}
+
+impl FromStr for Lint {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Lint::ALL
+            .into_iter()
+            .find(|lint| lint.id() == id)
+            .ok_or_else(|| anyhow!("unknown lint {}", id))
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Level {
+    /// do not check
+    Allow,
+    /// report as a [`Severity::Warning`]
+    Warn,
+    /// report as a [`Severity::Error`]
+    Deny,
+}
+
+/// Which lints to check and how to report them, the default level of each lint unless set.
+#[derive(Clone, Default, Debug)]
+pub struct Config {
+    levels: HashMap<Lint, Level>,
+}
+
+impl Config {
+    pub fn allow(self, lint: Lint) -> Self {
+        self.set(lint, Level::Allow)
+    }
+
+    pub fn warn(self, lint: Lint) -> Self {
+        self.set(lint, Level::Warn)
+    }
+
+    pub fn deny(self, lint: Lint) -> Self {
+        self.set(lint, Level::Deny)
+    }
+
+    pub fn set(mut self, lint: Lint, level: Level) -> Self {
+        self.levels.insert(lint, level);
+        self
+    }
+
+    pub fn level(&self, lint: Lint) -> Level {
+        self.levels
+            .get(&lint)
+            .copied()
+            .unwrap_or_else(|| lint.default_level())
+    }
+}
+
+/// Checks a project for the lints that are not allowed.
+pub fn lint(project: &ProjectXML, config: &Config) -> Report {
+    let mut problems = vec![];
+    for lint in Lint::ALL {
+        let severity = match config.level(lint) {
+            Level::Allow => continue,
+            Level::Warn => Severity::Warning,
+            Level::Deny => Severity::Error,
+        };
+        let mut report = |page, image, path: String, message: String| {
+            problems.push(Problem {
+                code: lint.id().into(),
+                severity,
+                page,
+                image,
+                path,
+                message,
+            })
+        };
+
+        match lint {
+            Lint::MixedDpi => {
+                let images = &project.images.image;
+                let mut counts: Vec<(&DPI, usize)> = vec![];
+                for image in images {
+                    match counts.iter_mut().find(|(dpi, _)| same_dpi(dpi, &image.dpi)) {
+                        Some((_, count)) => *count += 1,
+                        None => counts.push((&image.dpi, 1)),
+                    }
+                }
+                let most = counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+                let mut usual = counts.iter().filter(|(_, count)| *count == most);
+                match (usual.next(), usual.next()) {
+                    (Some((usual, _)), None) => {
+                        for image in images.iter().filter(|image| !same_dpi(usual, &image.dpi)) {
+                            report(
+                                None,
+                                Some(image.id),
+                                format!("project/images/image[id={}]/dpi", image.id),
+                                format!(
+                                    "scanned at {} DPI where most images are at {} DPI",
+                                    dpi(&image.dpi),
+                                    dpi(usual)
+                                ),
+                            );
+                        }
+                    }
+                    // no DPI is the usual one to single out the others by
+                    (Some(_), Some(_)) => {
+                        let dpis = counts
+                            .iter()
+                            .map(|(usual, _)| dpi(usual))
+                            .collect::<Vec<_>>();
+                        let (last, rest) = dpis.split_last().unwrap();
+                        report(
+                            None,
+                            None,
+                            "project/images".to_owned(),
+                            format!(
+                                "images are scanned at a mix of {} and {} DPI",
+                                rest.join(", "),
+                                last
+                            ),
+                        );
+                    }
+                    _ => {}
+                }
+            }
+            Lint::DeskewNearLimit => {
+                for page in project.filters.deskew.page.iter().flatten() {
+                    if page.params.angle.abs() >= DESKEW_NEAR_LIMIT {
+                        report(
+                            Some(page.id),
+                            None,
+                            format!("project/filters/deskew/page[id={}]/params/@angle", page.id),
+                            format!(
+                                "deskewed by {}°, close to the ±45° limit",
+                                format_double(page.params.angle)
+                            ),
+                        );
+                    }
+                }
+            }
+            Lint::SmallContent => {
+                for page in project.filters.select_content.page.iter().flatten() {
+                    let content = &page.params.content_rect;
+                    let page_rect = &page.params.page_rect;
+                    if page_rect.width <= Px(0f64) || page_rect.height <= Px(0f64) {
+                        report(
+                            Some(page.id),
+                            None,
+                            format!(
+                                "project/filters/select-content/page[id={}]/params/page-rect",
+                                page.id
+                            ),
+                            "the page is empty".to_owned(),
+                        );
+                        continue;
+                    }
+                    let coverage =
+                        (content.width / page_rect.width) * (content.height / page_rect.height);
+                    if coverage < SMALL_CONTENT {
+                        report(
+                            Some(page.id),
+                            None,
+                            format!(
+                                "project/filters/select-content/page[id={}]/params/content-rect",
+                                page.id
+                            ),
+                            format!("content covers {:.1}% of the page", coverage * 100f64),
+                        );
+                    }
+                }
+            }
+            Lint::WolfBoundsInverted => {
+                for page in project.filters.output.page.iter() {
+                    // settings not used by the mode come back when switching to it
+                    let bw = match &page.params.color_params {
+                        ColorParams::ColorOrGrayscale(params) => params.bw.as_ref(),
+                        params => params.bw(),
+                    };
+                    let Some(bw) = bw else {
+                        continue;
+                    };
+                    if bw.wolf_lower_bound >= bw.wolf_upper_bound {
+                        report(
+                            Some(page.id),
+                            None,
+                            format!(
+                                "project/filters/output/page[id={}]/params/color-params/bw",
+                                page.id
+                            ),
+                            format!(
+                                "Wolf lower bound {} is not below upper bound {}",
+                                bw.wolf_lower_bound, bw.wolf_upper_bound
+                            ),
+                        );
+                    }
+                }
+            }
+            Lint::InconsistentColorMode => {
+                let outputs = project
+                    .filters
+                    .output
+                    .page
+                    .iter()
+                    .map(|output| (output.id, output.params.color_params.color_mode()))
+                    .collect::<HashMap<_, _>>();
+                let files = project
+                    .images
+                    .image
+                    .iter()
+                    .map(|image| (image.id, image.file_id))
+                    .collect::<HashMap<_, _>>();
+                let modes = project
+                    .pages
+                    .page
+                    .iter()
+                    .filter_map(|page| {
+                        Some((
+                            page.id,
+                            *files.get(&page.image_id)?,
+                            *outputs.get(&page.id)?,
+                        ))
+                    })
+                    .collect::<Vec<_>>();
+                // a bill is scanned into a file of its own, and is only compared with itself
+                for bill in modes.chunk_by(|(_, a, _), (_, b, _)| a == b) {
+                    // a mode only stands out next to two pages sharing another one
+                    if bill.len() < 3 {
+                        continue;
+                    }
+                    let last = bill.len() - 1;
+                    for (index, (id, _, mode)) in bill.iter().enumerate() {
+                        // the first and last pages are compared with the two pages next to them
+                        let (neighbours, position) = match index {
+                            0 => ([1, 2], "before"),
+                            _ if index == last => ([last - 1, last - 2], "after"),
+                            _ => ([index - 1, index + 1], "between"),
+                        };
+                        let [(_, _, first), (_, _, second)] = neighbours.map(|i| &bill[i]);
+                        if first == second && mode != first {
+                            report(
+                                Some(*id),
+                                None,
+                                format!(
+                                    "project/filters/output/page[id={}]/params/color-params/@colorMode",
+                                    id
+                                ),
+                                format!(
+                                    "{} {} pages in {}",
+                                    color_mode(*mode),
+                                    position,
+                                    color_mode(*first)
+                                ),
+                            );
+                        }
+                    }
+                }
+            }
+            Lint::PageDetectionTolerance => {
+                let tolerance = project.filters.select_content.page_detection_tolerance;
+                let (min, max) = PAGE_DETECTION_TOLERANCE;
+                if !(min..=max).contains(&tolerance) {
+                    report(
+                        None,
+                        None,
+                        "project/filters/select-content/@pageDetectionTolerance".to_owned(),
+                        format!(
+                            "page detection tolerance {} is outside of the usual {} to {}",
+                            format_double(tolerance),
+                            format_double(min),
+                            format_double(max)
+                        ),
+                    );
+                }
+            }
+        }
+    }
+    Report { problems }
+}
+
+fn same_dpi(a: &DPI, b: &DPI) -> bool {
+    a.horizontal == b.horizontal && a.vertical == b.vertical
+}
+
+fn dpi(dpi: &DPI) -> String {
+    if dpi.horizontal == dpi.vertical {
+        format_double(dpi.horizontal.0)
+    } else {
+        format!(
+            "{}x{}",
+            format_double(dpi.horizontal.0),
+            format_double(dpi.vertical.0)
+        )
+    }
+}
+
+fn color_mode(mode: ColorMode) -> &'static str {
+    match mode {
+        ColorMode::BlackAndWhite => "black and white",
+        ColorMode::ColorOrGrayscale => "color or grayscale",
+        ColorMode::Mixed => "mixed",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::de::from_str;
+
+    use crate::{project::test::ORIGINAL_CONTENT, report::Severity, ProjectXML};
+
+    use super::{lint, Config, Lint};
+
+    #[test]
+    fn it_finds_nothing_in_a_clean_project() {
+        let project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        assert!(lint(&project, &Config::default()).is_empty());
+    }
+
+    #[test]
+    fn it_finds_suspicious_settings() {
+        let content = ORIGINAL_CONTENT
+            .replacen(
+                r#"<dpi horizontal="600" vertical="600"/></image><image fileId="5""#,
+                r#"<dpi horizontal="300" vertical="300"/></image><image fileId="5""#,
+                1,
+            )
+            .replacen(
+                r#"<page id="7"><params angle="0.125""#,
+                r#"<page id="7"><params angle="-42.5""#,
+                1,
+            )
+            .replacen(
+                r#"<content-rect height="2236" width="3328" x="116" y="364"/>"#,
+                r#"<content-rect height="236" width="328" x="116" y="364"/>"#,
+                1,
+            )
+            .replacen(r#"wolfLowerBound="1""#, r#"wolfLowerBound="254""#, 1)
+            .replacen(
+                r#"pageDetectionTolerance="0.1""#,
+                r#"pageDetectionTolerance="2""#,
+                1,
+            );
+        let project: ProjectXML = from_str(&content).unwrap();
+
+        let report = lint(&project, &Config::default());
+        assert_eq!(
+            report.to_string(),
+            "warning[mixed_dpi]: project/images: images are scanned at a mix of 300 and 600 DPI\n\
+             warning[deskew_near_limit]: project/filters/deskew/page[id=7]/params/@angle: deskewed by -42.5°, close to the ±45° limit\n\
+             warning[small_content]: project/filters/select-content/page[id=4]/params/content-rect: content covers 0.3% of the page\n\
+             warning[wolf_bounds_inverted]: project/filters/output/page[id=4]/params/color-params/bw: Wolf lower bound 254 is not below upper bound 254\n\
+             warning[page_detection_tolerance]: project/filters/select-content/@pageDetectionTolerance: page detection tolerance 2 is outside of the usual 0.01 to 0.5\n"
+        );
+
+        let config = Config::default()
+            .allow(Lint::MixedDpi)
+            .deny("small_content".parse().unwrap())
+            .deny(Lint::WolfBoundsInverted);
+        let report = lint(&project, &config);
+        assert_eq!(
+            report
+                .problems
+                .iter()
+                .map(|problem| (problem.code.as_ref(), problem.severity))
+                .collect::<Vec<_>>(),
+            vec![
+                ("deskew_near_limit", Severity::Warning),
+                ("small_content", Severity::Error),
+                ("wolf_bounds_inverted", Severity::Error),
+                ("page_detection_tolerance", Severity::Warning),
+            ]
+        );
+    }
+
+    #[test]
+    fn it_singles_out_images_at_an_unusual_dpi() {
+        let image = r#"<image fileId="5" fileImage="0" id="6" subPages="1"><size height="4961" width="7016"/><dpi horizontal="600" vertical="600"/></image>"#;
+        let content = ORIGINAL_CONTENT
+            .replacen(
+                r#"<dpi horizontal="600" vertical="600"/></image><image fileId="5""#,
+                r#"<dpi horizontal="300" vertical="300"/></image><image fileId="5""#,
+                1,
+            )
+            .replacen(
+                image,
+                &format!("{}{}", image, image.replace(r#"id="6""#, r#"id="10""#)),
+                1,
+            );
+        let project: ProjectXML = from_str(&content).unwrap();
+
+        let report = lint(&project, &Config::default());
+        assert_eq!(
+            report.to_string(),
+            "warning[mixed_dpi]: project/images/image[id=3]/dpi: scanned at 300 DPI where most images are at 600 DPI\n"
+        );
+    }
+
+    fn with_color_modes(modes: &[(u32, &str)]) -> ProjectXML {
+        with_bills(&[modes])
+    }
+
+    /// A project of bills, each scanned into a file of its own, with pages in those modes.
+    fn with_bills(bills: &[&[(u32, &str)]]) -> ProjectXML {
+        let image = &ORIGINAL_CONTENT
+            [ORIGINAL_CONTENT.find("<size").unwrap()..ORIGINAL_CONTENT.find("</image>").unwrap()];
+        let output = ORIGINAL_CONTENT
+            .split_once("<output>")
+            .unwrap()
+            .1
+            .split_once(r#"<page id="7">"#)
+            .unwrap()
+            .0
+            .strip_prefix(r#"<page id="4">"#)
+            .unwrap();
+        let mut files = String::new();
+        let mut images = String::new();
+        let mut pages = String::new();
+        let mut outputs = String::new();
+        for (bill, modes) in bills.iter().enumerate() {
+            let (file_id, image_id) = (100 + bill, 200 + bill);
+            files.push_str(&format!(
+                r#"<file dirId="1" id="{}" name="bill{}.tif"/>"#,
+                file_id, bill
+            ));
+            for (file_image, (id, mode)) in modes.iter().enumerate() {
+                images.push_str(&format!(
+                    r#"<image fileId="{}" fileImage="{}" id="{}" subPages="1">{}</image>"#,
+                    file_id,
+                    file_image,
+                    image_id * 10 + file_image,
+                    image
+                ));
+                pages.push_str(&format!(
+                    r#"<page id="{}" imageId="{}" subPage="single"/>"#,
+                    id,
+                    image_id * 10 + file_image
+                ));
+                outputs.push_str(&format!(
+                    r#"<page id="{}">{}"#,
+                    id,
+                    output.replace(
+                        r#"colorMode="colorOrGray""#,
+                        &format!(r#"colorMode="{}""#, mode)
+                    )
+                ));
+            }
+        }
+        let section = |name: &str| {
+            &ORIGINAL_CONTENT[ORIGINAL_CONTENT.find(&format!("<{}>", name)).unwrap()
+                ..ORIGINAL_CONTENT.find(&format!("</{}>", name)).unwrap()]
+        };
+        let content = ORIGINAL_CONTENT
+            .replacen(section("files"), &format!("<files>{}", files), 1)
+            .replacen(section("images"), &format!("<images>{}", images), 1)
+            .replacen(section("pages"), &format!("<pages>{}", pages), 1)
+            .replacen(section("output"), &format!("<output>{}", outputs), 1);
+        from_str(&content).unwrap()
+    }
+
+    #[test]
+    fn it_finds_pages_with_another_color_mode() {
+        let project = with_color_modes(&[
+            (4, "colorOrGray"),
+            (7, "bw"),
+            (8, "colorOrGray"),
+            (9, "mixed"),
+        ]);
+
+        let report = lint(&project, &Config::default());
+        assert_eq!(
+            report.to_string(),
+            "warning[inconsistent_color_mode]: project/filters/output/page[id=7]/params/color-params/@colorMode: black and white between pages in color or grayscale\n"
+        );
+    }
+
+    #[test]
+    fn it_finds_first_and_last_pages_with_another_color_mode() {
+        let project = with_color_modes(&[
+            (4, "bw"),
+            (7, "colorOrGray"),
+            (8, "colorOrGray"),
+            (9, "mixed"),
+        ]);
+
+        let report = lint(&project, &Config::default());
+        assert_eq!(
+            report.to_string(),
+            "warning[inconsistent_color_mode]: project/filters/output/page[id=4]/params/color-params/@colorMode: black and white before pages in color or grayscale\n\
+             warning[inconsistent_color_mode]: project/filters/output/page[id=9]/params/color-params/@colorMode: mixed after pages in color or grayscale\n"
+        );
+
+        let project = with_color_modes(&[(4, "bw"), (7, "colorOrGray")]);
+        assert!(lint(&project, &Config::default()).is_empty());
+    }
+
+    #[test]
+    fn it_compares_pages_within_their_bill() {
+        let project = with_bills(&[
+            &[(4, "bw"), (7, "bw"), (8, "bw")],
+            &[(9, "colorOrGray")],
+            &[(10, "bw"), (11, "bw")],
+            &[(12, "colorOrGray")],
+        ]);
+        assert!(lint(&project, &Config::default()).is_empty());
+
+        let project = with_bills(&[
+            &[(4, "bw"), (7, "bw")],
+            &[(8, "colorOrGray")],
+            &[(9, "bw"), (10, "colorOrGray"), (11, "bw")],
+        ]);
+        assert_eq!(
+            lint(&project, &Config::default()).to_string(),
+            "warning[inconsistent_color_mode]: project/filters/output/page[id=10]/params/color-params/@colorMode: color or grayscale between pages in black and white\n"
+        );
+    }
+
+    #[test]
+    fn it_reports_empty_pages() {
+        let content = ORIGINAL_CONTENT.replacen(
+            r#"<page-rect height="7024.310698751963" width="3832.297441614658" x="0" y="0"/>"#,
+            r#"<page-rect height="7024.310698751963" width="0" x="0" y="0"/>"#,
+            1,
+        );
+        let project: ProjectXML = from_str(&content).unwrap();
+
+        let report = lint(&project, &Config::default());
+        assert_eq!(
+            report.to_string(),
+            "warning[small_content]: project/filters/select-content/page[id=4]/params/page-rect: the page is empty\n"
+        );
+    }
+}
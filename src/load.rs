@@ -0,0 +1,395 @@
+use std::fmt::{self, Display};
+
+use anyhow::Result;
+
+use super::{
+    error::from_rewritten,
+    schema::Declarations,
+    xml::{Document, Element, Node},
+    ProjectXML,
+};
+
+/// How to treat attributes and elements the model does not know.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub enum Mode {
+    /// fail on anything unknown, for validation runs
+    #[default]
+    Strict,
+    /// skip anything unknown, reporting it as a [`Warning`]
+    Lenient,
+}
+
+/// Something in a project file that was skipped while loading it.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Unknown {
+    Attribute { name: String, value: String },
+    Element { name: String },
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Warning {
+    /// path of the element the unknown attribute or element was found on,
+    /// as in `project/filters/output/page[id=4]/params` or
+    /// `project/file-name-disambiguation/mapping[1]`
+    pub path: String,
+    pub unknown: Unknown,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.unknown {
+            Unknown::Attribute { name, value } => {
+                write!(f, "{}: unknown attribute {}=\"{}\"", self.path, name, value)
+            }
+            Unknown::Element { name } => write!(f, "{}: unknown element <{}>", self.path, name),
+        }
+    }
+}
+
+/// What [`save`] needs to write a loaded project back as it was: its layout, and
+/// the unknown attributes and elements of a leniently loaded project at every level of
+/// the model, to put back where they were found.
+#[derive(Clone, Default, Debug)]
+pub struct Preserved {
+    removed: Vec<Removed>,
+    document: Option<Document>,
+}
+
+impl Preserved {
+    pub fn is_empty(&self) -> bool {
+        self.removed.is_empty() && self.document.is_none()
+    }
+}
+
+#[derive(Debug)]
+pub struct Loaded {
+    pub project: ProjectXML,
+    pub warnings: Vec<Warning>,
+    pub preserved: Preserved,
+}
+
+/// Something found in a project file that the model does not declare.
+#[derive(Clone, PartialEq, Debug)]
+enum Undeclared {
+    Attribute(String, String),
+    Element(Element),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Removed {
+    /// path of the element it was found on
+    path: String,
+    /// its index among the attributes or child nodes of that element
+    index: usize,
+    undeclared: Undeclared,
+}
+
+/// Removes every attribute and element below `root` that the model does not declare,
+/// in document order.
+fn remove_undeclared(root: &mut Element) -> Vec<Removed> {
+    let mut removed = vec![];
+    let path = root.name.clone();
+    remove_undeclared_below(root, &Declarations::project(), &path, &mut removed);
+    removed
+}
+
+fn remove_undeclared_below(
+    element: &mut Element,
+    declarations: &Declarations,
+    path: &str,
+    removed: &mut Vec<Removed>,
+) {
+    for (index, (key, value)) in std::mem::take(&mut element.attributes)
+        .into_iter()
+        .enumerate()
+    {
+        if declarations.has_attribute(&key) {
+            element.attributes.push((key, value));
+        } else {
+            removed.push(Removed {
+                path: path.to_owned(),
+                index,
+                undeclared: Undeclared::Attribute(key, value),
+            });
+        }
+    }
+
+    let mut segments = element.child_segments().into_iter();
+    let mut children = vec![];
+    for (index, node) in std::mem::take(&mut element.children)
+        .into_iter()
+        .enumerate()
+    {
+        match node {
+            Node::Element(mut child) => match declarations.child(&child.name) {
+                Some(declarations) => {
+                    let path = format!("{}/{}", path, segments.next().unwrap());
+                    remove_undeclared_below(&mut child, &declarations, &path, removed);
+                    children.push(Node::Element(child));
+                }
+                None => {
+                    segments.next();
+                    removed.push(Removed {
+                        path: path.to_owned(),
+                        index,
+                        undeclared: Undeclared::Element(child),
+                    })
+                }
+            },
+            text => children.push(text),
+        }
+    }
+    element.children = children;
+}
+
+/// Reads a project file. In [`Mode::Lenient`], attributes and elements written by
+/// newer ScanTailor builds are skipped instead of making the whole project unreadable.
+pub fn load(content: &str, mode: Mode) -> Result<Loaded> {
+    match mode {
+        Mode::Strict => Ok(Loaded {
+            project: content.parse()?,
+            warnings: vec![],
+            preserved: Preserved {
+                removed: vec![],
+                document: Some(Document::parse(content)?),
+            },
+        }),
+        Mode::Lenient => {
+            let mut root = Element::parse(content)?;
+            let removed = remove_undeclared(&mut root);
+            let warnings = removed
+                .iter()
+                .map(|removed| Warning {
+                    path: removed.path.clone(),
+                    unknown: match &removed.undeclared {
+                        Undeclared::Attribute(name, value) => Unknown::Attribute {
+                            name: name.clone(),
+                            value: value.clone(),
+                        },
+                        Undeclared::Element(element) => Unknown::Element {
+                            name: element.name.clone(),
+                        },
+                    },
+                })
+                .collect();
+
+            Ok(Loaded {
+                project: from_rewritten(&root.to_string(), content)?,
+                warnings,
+                preserved: Preserved {
+                    removed,
+                    document: Some(Document::parse(content)?),
+                },
+            })
+        }
+    }
+}
+
+/// Writes a project file, putting back what was preserved when loading it.
+/// Anything found on an element that no longer exists, such as the entry of a deleted page,
+/// is left out. A loaded project keeps its indentation, new elements are indented like it.
+pub fn save(project: &ProjectXML, preserved: &Preserved) -> Result<String> {
+    let content = quick_xml::se::to_string(project)?;
+    if preserved.is_empty() {
+        return Ok(content);
+    }
+
+    let mut root = Element::parse(&content)?;
+    // in document order, so that every index counts what precedes it in the original
+    for removed in preserved.removed.iter() {
+        let Some(element) = root.find_path_mut(&removed.path) else {
+            continue;
+        };
+        match &removed.undeclared {
+            Undeclared::Attribute(key, value) => {
+                let index = removed.index.min(element.attributes.len());
+                element
+                    .attributes
+                    .insert(index, (key.clone(), value.clone()));
+            }
+            Undeclared::Element(child) => {
+                let index = removed.index.min(element.children.len());
+                element.children.insert(index, Node::Element(child.clone()));
+            }
+        }
+    }
+    Ok(match &preserved.document {
+        Some(document) => document.write(root),
+        None => root.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        error::ParseError,
+        project::test::{ORIGINAL_CONTENT, ZONED_CONTENT},
+    };
+
+    use quick_xml::{events::Event, Reader, Writer};
+
+    use super::{load, save, Mode, Unknown, Warning};
+
+    fn newer_content() -> String {
+        ORIGINAL_CONTENT
+            .replacen(r#"version="3">"#, r#"uiTheme="dark" version="3">"#, 1)
+            .replacen("</deskew>", r#"<auto-rotate enabled="1"/></deskew>"#, 1)
+    }
+
+    /// `content` laid out as ScanTailor writes it, with `QDomDocument::save(stream, 2)`.
+    fn indented(content: &str) -> String {
+        let mut reader = Reader::from_str(content);
+        let mut writer = Writer::new_with_indent(vec![], b' ', 2);
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Eof => break,
+                event => writer.write_event(event).unwrap(),
+            }
+        }
+        String::from_utf8(writer.into_inner()).unwrap() + "\n"
+    }
+
+    #[test]
+    fn it_fails_on_unknown_fields_by_default() {
+        assert!(load(&newer_content(), Mode::default()).is_err());
+    }
+
+    #[test]
+    fn it_skips_unknown_fields_leniently() {
+        let loaded = load(&newer_content(), Mode::Lenient).unwrap();
+        assert_eq!(
+            loaded.warnings,
+            vec![
+                Warning {
+                    path: "project".to_owned(),
+                    unknown: Unknown::Attribute {
+                        name: "uiTheme".to_owned(),
+                        value: "dark".to_owned(),
+                    },
+                },
+                Warning {
+                    path: "project/filters/deskew".to_owned(),
+                    unknown: Unknown::Element {
+                        name: "auto-rotate".to_owned(),
+                    },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn it_locates_errors_in_the_original_content() {
+        let content = newer_content().replace("><", ">\n<").replacen(
+            r#"<m11>-0.00218165983433677</m11>"#,
+            "<m11>tilted</m11>",
+            1,
+        );
+        let error = load(&content, Mode::Lenient).unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(
+            error.path,
+            "project/filters/output/page[id=4]/output-params/image/partial-xform/m11"
+        );
+        assert_eq!(
+            content.lines().nth(error.line - 1).unwrap(),
+            "<m11>tilted</m11>"
+        );
+    }
+
+    #[test]
+    fn it_has_no_warnings_for_known_fields() {
+        let loaded = load(ORIGINAL_CONTENT, Mode::Lenient).unwrap();
+        assert!(loaded.warnings.is_empty());
+    }
+
+    #[test]
+    fn it_writes_back_unmodified_projects_as_they_were() {
+        for content in [ORIGINAL_CONTENT, ZONED_CONTENT] {
+            for mode in [Mode::Strict, Mode::Lenient] {
+                let loaded = load(content, mode).unwrap();
+                assert_eq!(save(&loaded.project, &loaded.preserved).unwrap(), content);
+            }
+        }
+    }
+
+    #[test]
+    fn it_writes_back_unknown_fields() {
+        let content = newer_content().replacen(
+            r#"<image blackOnWhite="1" depthPerception="2" despeckleLevel="1"><size"#,
+            r#"<image blackOnWhite="1" depthPerception="2" despeckleLevel="1" gamma="2.2"><size"#,
+            1,
+        );
+        let mut loaded = load(&content, Mode::Lenient).unwrap();
+
+        let deskew = &mut loaded.project.filters.deskew.page.as_mut().unwrap()[0];
+        deskew.params.angle = 0.25;
+
+        let saved = save(&loaded.project, &loaded.preserved).unwrap();
+        assert_eq!(
+            saved,
+            content.replacen(r#"angle="0.125""#, r#"angle="0.25""#, 1)
+        );
+    }
+
+    #[test]
+    fn it_writes_back_unknown_fields_on_the_sibling_they_were_found_on() {
+        let content = ORIGINAL_CONTENT.replacen(
+            r#"<mapping file="5" label="0"/>"#,
+            r#"<mapping extra="x" file="5" label="0"><note/></mapping>"#,
+            1,
+        );
+        let loaded = load(&content, Mode::Lenient).unwrap();
+        assert_eq!(
+            loaded
+                .warnings
+                .iter()
+                .map(|warning| warning.to_string())
+                .collect::<Vec<_>>(),
+            vec![
+                "project/file-name-disambiguation/mapping[1]: unknown attribute extra=\"x\"",
+                "project/file-name-disambiguation/mapping[1]: unknown element <note>",
+            ]
+        );
+        assert_eq!(save(&loaded.project, &loaded.preserved).unwrap(), content);
+    }
+
+    #[test]
+    fn it_keeps_the_layout_of_indented_projects() {
+        for (content, mode) in [
+            (indented(ORIGINAL_CONTENT), Mode::Strict),
+            (indented(&newer_content()), Mode::Lenient),
+        ] {
+            let mut loaded = load(&content, mode).unwrap();
+            assert_eq!(save(&loaded.project, &loaded.preserved).unwrap(), content);
+
+            let deskew = &mut loaded.project.filters.deskew.page.as_mut().unwrap()[0];
+            deskew.params.angle = 0.25;
+            assert_eq!(
+                save(&loaded.project, &loaded.preserved).unwrap(),
+                content.replacen(r#"angle="0.125""#, r#"angle="0.25""#, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn it_indents_new_elements_like_the_rest() {
+        let page = r#"<page id="7"><params angle="0.125" mode="auto"><dependencies><rotation degrees="90"/><page-outline><point x="1150" y="0"/><point x="4960" y="0"/><point x="4960" y="7016"/><point x="1150" y="7016"/><point x="1150" y="0"/></page-outline></dependencies></params></page><image-settings>"#;
+        let without = ORIGINAL_CONTENT.replacen(page, "<image-settings>", 1);
+
+        let mut loaded = load(&indented(ORIGINAL_CONTENT), Mode::Strict).unwrap();
+        let pages = loaded.project.filters.deskew.page.as_mut().unwrap();
+        let removed = pages.pop().unwrap();
+        assert_eq!(
+            save(&loaded.project, &loaded.preserved).unwrap(),
+            indented(&without)
+        );
+
+        let mut loaded = load(&indented(&without), Mode::Strict).unwrap();
+        let pages = loaded.project.filters.deskew.page.as_mut().unwrap();
+        pages.push(removed);
+        assert_eq!(
+            save(&loaded.project, &loaded.preserved).unwrap(),
+            indented(ORIGINAL_CONTENT)
+        );
+    }
+}
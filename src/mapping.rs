@@ -0,0 +1,411 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    common::{Point, Size, DPI},
+    filters::{
+        common::{FilledOutline, Outline, Rect},
+        fix_orientation::Degrees,
+        page_layout::{self, HorizontalAlignment, VerticalAlignment},
+    },
+    id::PageId,
+    units::{Dpi, Mm, Px},
+    ProjectXML,
+};
+
+use super::transform::Transform;
+
+/// How the pixels of a page move from the source image to the output image, stage by
+/// stage, as ScanTailor lays them out from what the filters store.
+#[derive(Clone, Debug)]
+pub struct PageTransform {
+    pub page: PageId,
+    /// equalizes the horizontal and vertical DPI of the source image, as ScanTailor
+    /// scales to the lower of both before anything else
+    pub pre_scale: Transform,
+    /// turns the source image as fix orientation says, keeping it at the origin
+    pub orientation: Transform,
+    /// where the page is on the turned image, as page split cut it out
+    pub page_outline: FilledOutline,
+    /// turns the page by the deskew angle about its center, keeping it at the origin
+    pub deskew: Transform,
+    /// scales to the output DPI and places the content where the page layout puts it
+    pub layout: Transform,
+}
+
+impl PageTransform {
+    /// Builds the stages of a page from the params stored for it.
+    ///
+    /// ScanTailor sizes every output page aligned with the others to the largest one and
+    /// aligns the content of each within it, as recorded once the output was generated.
+    /// Until then, it is worked out from the page layout of every page, which is not
+    /// possible for the automatic and original alignments, found from the image content.
+    pub fn new(project: &ProjectXML, page_id: PageId) -> Result<Self> {
+        let page = project
+            .pages
+            .page
+            .iter()
+            .find(|page| page.id == page_id)
+            .ok_or_else(|| anyhow!("no page {}", page_id))?;
+        let image = project
+            .images
+            .image
+            .iter()
+            .find(|image| image.id == page.image_id)
+            .ok_or_else(|| anyhow!("no image {} for page {}", page.image_id, page_id))?;
+        let filters = &project.filters;
+        check_dpi(&image.dpi, page_id)?;
+
+        let dpi = image.dpi.horizontal.min(image.dpi.vertical);
+        let pre_scale = Transform::scaling(dpi / image.dpi.horizontal, dpi / image.dpi.vertical);
+        let source = pre_scale.map_rect(&Rect::new(
+            Px(0f64),
+            Px(0f64),
+            Px(image.size.width.into()),
+            Px(image.size.height.into()),
+        ));
+
+        let degrees = filters
+            .fix_orientation
+            .image
+            .iter()
+            .flatten()
+            .find(|entry| entry.id == image.id)
+            .map_or(0f64, |entry| match entry.rotation.degrees {
+                Degrees::_90 => 90f64,
+                Degrees::_180 => 180f64,
+                Degrees::_270 => 270f64,
+            });
+        let orientation = to_origin(Transform::rotation(degrees), &source);
+
+        let deskew = filters
+            .deskew
+            .page
+            .iter()
+            .flatten()
+            .find(|entry| entry.id == page_id)
+            .ok_or_else(|| anyhow!("page {} is not deskewed", page_id))?;
+        let Outline::Filled(page_outline) = &deskew.params.dependencies.page_outline else {
+            return Err(anyhow!("page {} has no outline", page_id));
+        };
+        let bounds = page_outline
+            .bounding_box()
+            .ok_or_else(|| anyhow!("page {} has no outline", page_id))?;
+        let (cx, cy) = (
+            (bounds.left() + bounds.width / 2f64).0,
+            (bounds.top() + bounds.height / 2f64).0,
+        );
+        let deskew = to_origin(
+            Transform::translation(-cx, -cy)
+                .then(&Transform::rotation(deskew.params.angle))
+                .then(&Transform::translation(cx, cy)),
+            &bounds,
+        );
+
+        let content = filters
+            .select_content
+            .page
+            .iter()
+            .flatten()
+            .find(|entry| entry.id == page_id)
+            .ok_or_else(|| anyhow!("page {} has no content selected", page_id))?
+            .params
+            .content_rect;
+        let output = filters.output.page.iter().find(|entry| entry.id == page_id);
+        let layout = match output.and_then(|output| output.output_params.as_ref()) {
+            Some(output_params) => {
+                let image = &output_params.image;
+                check_dpi(&image.dpi, page_id)?;
+                let scale = to_dpi(dpi, &image.dpi);
+                let content = scale.map_point(&Point::new(content.x, content.y));
+                scale.then(&Transform::translation(
+                    (image.content_rect.x - content.x).0,
+                    (image.content_rect.y - content.y).0,
+                ))
+            }
+            None => {
+                let output_dpi = &output
+                    .ok_or_else(|| anyhow!("page {} has no output params", page_id))?
+                    .params
+                    .dpi;
+                check_dpi(output_dpi, page_id)?;
+                let (left, top) = content_offset(project, page_id)?;
+                let scale = to_dpi(dpi, output_dpi);
+                let content = scale.map_point(&Point::new(content.x, content.y));
+                scale.then(&Transform::translation(
+                    (left.to_px(output_dpi.horizontal) - content.x).0,
+                    (top.to_px(output_dpi.vertical) - content.y).0,
+                ))
+            }
+        };
+
+        Ok(PageTransform {
+            page: page_id,
+            pre_scale,
+            orientation,
+            page_outline: page_outline.clone(),
+            deskew,
+            layout,
+        })
+    }
+
+    /// Maps source image pixels to output image pixels.
+    pub fn to_output(&self) -> Transform {
+        self.pre_scale
+            .then(&self.orientation)
+            .then(&self.deskew)
+            .then(&self.layout)
+    }
+
+    /// Maps output image pixels back to source image pixels.
+    pub fn to_source(&self) -> Transform {
+        // none of the stages flattens the plane, as every DPI was checked to be positive
+        self.to_output().inverse().unwrap()
+    }
+
+    /// Where a point of the source image ends up on the output, unless it is not on
+    /// this page, as when the image was split in two.
+    pub fn map_to_output(&self, point: &Point) -> Option<Point> {
+        let oriented = self.pre_scale.then(&self.orientation).map_point(point);
+        self.page_outline
+            .contains(&oriented)
+            .then(|| self.deskew.then(&self.layout).map_point(&oriented))
+    }
+
+    pub fn map_to_source(&self, point: &Point) -> Point {
+        self.to_source().map_point(point)
+    }
+
+    /// The area of the output covering a rectangle of the source image.
+    pub fn map_rect_to_output(&self, rect: &Rect) -> Rect {
+        self.to_output().map_rect(rect)
+    }
+
+    /// The area of the source image covering a rectangle of the output.
+    pub fn map_rect_to_source(&self, rect: &Rect) -> Rect {
+        self.to_source().map_rect(rect)
+    }
+}
+
+/// Where the content of a page goes on its output page, from its top left corner, as
+/// ScanTailor lays it out: the hard margins, and as much of the room left by the
+/// largest page aligned with it as alignment puts before the content.
+fn content_offset(project: &ProjectXML, page_id: PageId) -> Result<(Mm, Mm)> {
+    let layouts = project
+        .filters
+        .page_layout
+        .page
+        .as_deref()
+        .unwrap_or_default();
+    let params = &layouts
+        .iter()
+        .find(|entry| entry.id == page_id)
+        .ok_or_else(|| anyhow!("page {} has no layout", page_id))?
+        .params;
+    let margins = &params.hard_margins_mm;
+    let alignment = &params.alignment;
+    if alignment.null {
+        return Ok((margins.left, margins.top));
+    }
+
+    let hard_size = |params: &page_layout::Params| Size {
+        width: params.content_size.width
+            + params.hard_margins_mm.left
+            + params.hard_margins_mm.right,
+        height: params.content_size.height
+            + params.hard_margins_mm.top
+            + params.hard_margins_mm.bottom,
+    };
+    let size = hard_size(params);
+    let largest = layouts
+        .iter()
+        .filter(|entry| !entry.params.alignment.null)
+        .map(|entry| hard_size(&entry.params))
+        .fold(size, |largest, size| Size {
+            width: Mm(largest.width.0.max(size.width.0)),
+            height: Mm(largest.height.0.max(size.height.0)),
+        });
+    let (room_x, room_y) = (largest.width - size.width, largest.height - size.height);
+
+    let left = match alignment.horizontal {
+        HorizontalAlignment::Left => Mm(0f64),
+        HorizontalAlignment::Center => room_x / 2f64,
+        HorizontalAlignment::Right => room_x,
+        HorizontalAlignment::Auto | HorizontalAlignment::Original => {
+            return Err(anyhow!(
+                "page {} is aligned from its content, which only the output params record",
+                page_id
+            ))
+        }
+    };
+    let top = match alignment.vertical {
+        VerticalAlignment::Top => Mm(0f64),
+        VerticalAlignment::Center => room_y / 2f64,
+        VerticalAlignment::Bottom => room_y,
+        VerticalAlignment::Auto | VerticalAlignment::Original => {
+            return Err(anyhow!(
+                "page {} is aligned from its content, which only the output params record",
+                page_id
+            ))
+        }
+    };
+    Ok((margins.left + left, margins.top + top))
+}
+
+/// Zero or negative DPIs would flatten the page, with no way back to the source image.
+fn check_dpi(dpi: &DPI, page_id: PageId) -> Result<()> {
+    if dpi.horizontal > Dpi(0f64) && dpi.vertical > Dpi(0f64) {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "page {} has a DPI of {}x{}",
+            page_id,
+            dpi.horizontal.0,
+            dpi.vertical.0
+        ))
+    }
+}
+
+/// Follows a transform with the translation bringing what it makes of `rect` back to
+/// the origin.
+fn to_origin(transform: Transform, rect: &Rect) -> Transform {
+    let bounds = transform.map_rect(rect);
+    transform.then(&Transform::translation(-bounds.x.0, -bounds.y.0))
+}
+
+fn to_dpi(from: Dpi, to: &DPI) -> Transform {
+    Transform::scaling(to.horizontal / from, to.vertical / from)
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::de::from_str;
+
+    use crate::{
+        common::Point,
+        filters::common::{Outline, Rect},
+        id::PageId,
+        project::test::ORIGINAL_CONTENT,
+        transform::Transform,
+        units::Px,
+        ProjectXML,
+    };
+
+    use super::PageTransform;
+
+    fn assert_near(a: &Point, b: &Point) {
+        assert!(
+            (a.x - b.x).abs() < Px(1e-6) && (a.y - b.y).abs() < Px(1e-6),
+            "{:?} != {:?}",
+            a,
+            b
+        );
+    }
+
+    #[test]
+    fn it_maps_pages_like_scantailor() {
+        let project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        for (index, id) in [4, 7].into_iter().enumerate() {
+            let transform = PageTransform::new(&project, PageId(id)).unwrap();
+
+            // the deskewed outline select content stores
+            let select_content = &project.filters.select_content.page.as_ref().unwrap()[index];
+            let Outline::Filled(rotated) = &select_content.params.dependencies.rotated_page_outline
+            else {
+                panic!();
+            };
+            for (point, expected) in transform.page_outline.point.iter().zip(&rotated.point) {
+                assert_near(&transform.deskew.map_point(point), expected);
+            }
+
+            // the crop area and partial transform output stores
+            let output = project.filters.output.page[index]
+                .output_params
+                .as_ref()
+                .unwrap();
+            let Outline::Filled(crop_area) = &output.image.crop_area else {
+                panic!();
+            };
+            let to_output = transform.to_output();
+            let oriented_to_output = transform.deskew.then(&transform.layout);
+            for (point, expected) in transform.page_outline.point.iter().zip(&crop_area.point) {
+                assert_near(&oriented_to_output.map_point(point), expected);
+            }
+            let partial = Transform::from(&output.image.partial_transform);
+            for (a, b) in [
+                (to_output.m11, partial.m11),
+                (to_output.m12, partial.m12),
+                (to_output.m21, partial.m21),
+                (to_output.m22, partial.m22),
+            ] {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn it_maps_back_and_forth() {
+        let project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        let transform = PageTransform::new(&project, PageId(4)).unwrap();
+
+        // the source image is turned by 90°, so its top right corner ends up at the top
+        // left, left of the page
+        assert_near(
+            &transform
+                .orientation
+                .map_point(&Point::new(Px(0f64), Px(4961f64))),
+            &Point::new(Px(0f64), Px(0f64)),
+        );
+        assert_eq!(
+            transform.map_to_output(&Point::new(Px(0f64), Px(4961f64))),
+            None
+        );
+
+        let point = Point::new(Px(3000f64), Px(2000f64));
+        let output = transform.map_to_output(&point).unwrap();
+        assert_near(&transform.map_to_source(&output), &point);
+
+        let rect = Rect::new(Px(3000f64), Px(2000f64), Px(100f64), Px(50f64));
+        let mapped = transform.map_rect_to_output(&rect);
+        assert!((mapped.width.0 - 50.2).abs() < 0.5 && (mapped.height.0 - 100.1).abs() < 0.5);
+        let back = transform.map_rect_to_source(&mapped);
+        assert!(back.contains(&Rect::new(Px(3000.5), Px(2000.5), Px(99f64), Px(49f64))));
+
+        assert!(PageTransform::new(&project, PageId(5)).is_err());
+    }
+
+    #[test]
+    fn it_lays_out_pages_before_the_output_is_generated() {
+        let mut project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        let generated = [4, 7].map(|id| PageTransform::new(&project, PageId(id)).unwrap());
+        for page in project.filters.output.page.iter_mut() {
+            page.output_params = None;
+        }
+
+        // ScanTailor rounds the content rect to whole pixels
+        for (id, generated) in [4, 7].into_iter().zip(generated) {
+            let layout = PageTransform::new(&project, PageId(id)).unwrap().layout;
+            assert!((layout.dx - generated.layout.dx).abs() < 1f64);
+            assert!((layout.dy - generated.layout.dy).abs() < 1f64);
+        }
+
+        let content = ORIGINAL_CONTENT.replacen(
+            r#"<alignment hor="center" null="0" vert="center"/>"#,
+            r#"<alignment hor="auto" null="0" vert="center"/>"#,
+            1,
+        );
+        let mut project: ProjectXML = from_str(&content).unwrap();
+        project.filters.output.page[0].output_params = None;
+        assert!(PageTransform::new(&project, PageId(4)).is_err());
+    }
+
+    #[test]
+    fn it_rejects_flat_dpis() {
+        let content = ORIGINAL_CONTENT.replacen(
+            r#"<dpi horizontal="600" vertical="600"/>"#,
+            r#"<dpi horizontal="0" vertical="600"/>"#,
+            1,
+        );
+        let project: ProjectXML = from_str(&content).unwrap();
+        assert!(PageTransform::new(&project, PageId(4)).is_err());
+    }
+}
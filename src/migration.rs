@@ -0,0 +1,772 @@
+use std::fmt::{self, Display};
+
+use anyhow::{anyhow, Context, Result};
+
+use super::{error::from_rewritten, number::format_double, xml::Element, ProjectXML};
+
+/// The project format version modelled by this crate, as written by ScanTailor Advanced.
+pub const CURRENT_VERSION: u8 = 3;
+
+/// The ScanTailor fork a project was written by.
+/// Projects of versions below [`CURRENT_VERSION`] were written by one of the older forks,
+/// told apart by what they store in the output params, the others by ScanTailor Advanced.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Dialect {
+    /// the original ScanTailor 0.9.x, before version 3
+    ScanTailor,
+    /// ScanTailor Enhanced, before version 3, which adds picture shapes to the output stage
+    Enhanced,
+    /// ScanTailor Experimental, before version 3, which adds dewarping to the output stage
+    Experimental,
+    /// ScanTailor Advanced, version 3 onwards, the layout modelled by this crate
+    Advanced,
+}
+
+impl Dialect {
+    fn detect(root: &mut Element) -> Self {
+        // a version that does not parse is reported when migrating
+        if matches!(version(root), Ok(version) if version >= CURRENT_VERSION) {
+            return Dialect::Advanced;
+        }
+
+        let params = root.find_all_mut("filters/output/page/params");
+        if params.iter().any(|params| {
+            params.attribute("dewarpingMode").is_some()
+                || params.child("distortion-model").is_some()
+        }) {
+            Dialect::Experimental
+        } else if params
+            .iter()
+            .any(|params| params.attribute("pictureShape").is_some())
+        {
+            Dialect::Enhanced
+        } else {
+            Dialect::ScanTailor
+        }
+    }
+}
+
+/// Something a migration had to change or could not carry over.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Note {
+    /// a field absent from the source was set to ScanTailor Advanced's default
+    Defaulted {
+        path: String,
+        field: String,
+        value: String,
+    },
+    /// a field was carried over under another name or representation
+    Converted {
+        path: String,
+        from: String,
+        to: String,
+    },
+    /// a field has no equivalent in the current layout and was dropped
+    Dropped {
+        path: String,
+        field: String,
+        value: String,
+    },
+}
+
+impl Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Note::Defaulted { path, field, value } => {
+                write!(f, "{}: {} defaulted to {}", path, field, value)
+            }
+            Note::Converted { path, from, to } => {
+                write!(f, "{}: {} converted to {}", path, from, to)
+            }
+            Note::Dropped { path, field, value } => {
+                write!(
+                    f,
+                    "{}: {} ({}) has no equivalent and was dropped",
+                    path, field, value
+                )
+            }
+        }
+    }
+}
+
+/// A project upgraded to the current layout.
+#[derive(Debug)]
+pub struct Migrated {
+    pub project: ProjectXML,
+    pub dialect: Dialect,
+    /// the version the project had before it was migrated
+    pub version: u8,
+    pub notes: Vec<Note>,
+}
+
+/// Parses a project written by any known ScanTailor fork or version,
+/// upgrading it to the layout modelled by [`ProjectXML`].
+/// Projects of a version newer than [`CURRENT_VERSION`] are refused.
+pub fn migrate(content: &str) -> Result<Migrated> {
+    let mut root = Element::parse(content)?;
+    let dialect = Dialect::detect(&mut root);
+    migrate_element(content, root, dialect)
+}
+
+/// Same as [`migrate`], for when the fork that wrote the project is known.
+pub fn migrate_from(content: &str, dialect: Dialect) -> Result<Migrated> {
+    migrate_element(content, Element::parse(content)?, dialect)
+}
+
+fn migrate_element(content: &str, mut root: Element, dialect: Dialect) -> Result<Migrated> {
+    let version = version(&root)?;
+    let mut migration = Migration { notes: vec![] };
+
+    // what a newer version added would be dropped without a trace when saving it as
+    // the current version
+    if version > CURRENT_VERSION {
+        return Err(anyhow!(
+            "project version {} is newer than {}, the latest this crate can migrate",
+            version,
+            CURRENT_VERSION
+        ));
+    }
+    if dialect != Dialect::Advanced {
+        migration.upgrade_legacy(&mut root);
+    }
+    migration.fill_defaults(&mut root);
+    root.set_attribute("version", &CURRENT_VERSION.to_string());
+
+    let project = from_rewritten(&root.to_string(), content)
+        .with_context(|| format!("failed to read migrated {:?} project", dialect))?;
+
+    Ok(Migrated {
+        project,
+        dialect,
+        version,
+        notes: migration.notes,
+    })
+}
+
+fn version(root: &Element) -> Result<u8> {
+    // the version attribute predates every fork, projects without one are the oldest
+    match root.attribute("version") {
+        Some(version) => version
+            .parse()
+            .with_context(|| format!("invalid project version {:?}", version)),
+        None => Ok(1),
+    }
+}
+
+// attributes and children the current layout knows for output params;
+// anything else left on a legacy element after conversion is dropped
+const OUTPUT_PARAMS_ATTRIBUTES: &[&str] = &["blackOnWhite", "depthPerception", "despeckleLevel"];
+const OUTPUT_PARAMS_CHILDREN: &[&str] = &[
+    "distortion-model",
+    "picture-shape-options",
+    "dewarping-options",
+    "dpi",
+    "color-params",
+    "splitting",
+];
+
+struct Migration {
+    notes: Vec<Note>,
+}
+
+impl Migration {
+    fn default_attribute(&mut self, element: &mut Element, path: &str, key: &str, value: &str) {
+        if element.attribute(key).is_none() {
+            element.set_attribute(key, value);
+            element.sort_attributes();
+            self.notes.push(Note::Defaulted {
+                path: path.to_owned(),
+                field: key.to_owned(),
+                value: value.to_owned(),
+            });
+        }
+    }
+
+    fn default_child(
+        &mut self,
+        element: &mut Element,
+        path: &str,
+        preceding: &[&str],
+        child: Element,
+    ) {
+        if element.child(&child.name).is_none() {
+            self.notes.push(Note::Defaulted {
+                path: path.to_owned(),
+                field: child.name.clone(),
+                value: child.to_string(),
+            });
+            element.insert_child_after(preceding, child);
+        }
+    }
+
+    fn rename_attribute(&mut self, element: &mut Element, path: &str, from: &str, to: &str) {
+        if let Some(value) = element.remove_attribute(from) {
+            element.set_attribute(to, &value);
+            element.sort_attributes();
+            self.notes.push(Note::Converted {
+                path: path.to_owned(),
+                from: from.to_owned(),
+                to: to.to_owned(),
+            });
+        }
+    }
+
+    fn drop_unknown(
+        &mut self,
+        element: &mut Element,
+        path: &str,
+        attributes: &[&str],
+        children: &[&str],
+    ) {
+        for (key, value) in element.attributes.iter() {
+            if !attributes.contains(&key.as_str()) {
+                self.notes.push(Note::Dropped {
+                    path: path.to_owned(),
+                    field: key.clone(),
+                    value: value.clone(),
+                });
+            }
+        }
+        element
+            .attributes
+            .retain(|(key, _)| attributes.contains(&key.as_str()));
+
+        let dropped = element
+            .elements()
+            .filter(|child| !children.contains(&child.name.as_str()))
+            .map(|child| (child.name.clone(), child.to_string()))
+            .collect::<Vec<_>>();
+        for (name, value) in dropped {
+            element.remove_child(&name);
+            self.notes.push(Note::Dropped {
+                path: path.to_owned(),
+                field: name,
+                value,
+            });
+        }
+    }
+
+    /// Upgrades the layout shared by ScanTailor and its Enhanced and Experimental forks.
+    fn upgrade_legacy(&mut self, root: &mut Element) {
+        self.default_attribute(root, "project", "layoutDirection", "LTR");
+
+        for filter in ["fix-orientation", "deskew"] {
+            for element in root.find_all_mut(&format!("filters/{}", filter)) {
+                let path = format!("project/filters/{}", filter);
+                self.default_child(
+                    element,
+                    &path,
+                    &["image", "page"],
+                    Element::new("image-settings"),
+                );
+            }
+        }
+
+        self.upgrade_select_content(root);
+        self.upgrade_page_layout(root);
+        self.upgrade_output(root);
+    }
+
+    fn upgrade_select_content(&mut self, root: &mut Element) {
+        for select_content in root.find_all_mut("filters/select-content") {
+            let parent = "project/filters/select-content";
+            self.default_attribute(select_content, parent, "pageDetectionTolerance", "0.1");
+
+            for page in select_content.children_mut("page") {
+                let page_path = page.path(parent);
+                let Some(params) = page.child_mut("params") else {
+                    continue;
+                };
+                let path = format!("{}/params", page_path);
+
+                // there was a single detection mode, for the content
+                self.rename_attribute(params, &path, "mode", "contentDetectionMode");
+                self.default_attribute(params, &path, "contentDetectionMode", "auto");
+                self.default_attribute(params, &path, "fineTuneCorners", "0");
+                self.default_attribute(params, &path, "pageDetectionMode", "disabled");
+
+                if params.child("page-rect").is_none() {
+                    let page_rect = params
+                        .child("dependencies")
+                        .and_then(|dependencies| dependencies.child("rotated-page-outline"))
+                        .and_then(bounding_rect)
+                        .unwrap_or_else(|| {
+                            let mut rect = params
+                                .child("content-rect")
+                                .cloned()
+                                .unwrap_or_else(|| Element::new("content-rect"));
+                            rect.name = "page-rect".to_owned();
+                            rect
+                        });
+                    self.default_child(params, &path, &["content-rect"], page_rect);
+                }
+
+                let mut dependencies_params = Element::new("params");
+                for key in [
+                    "contentDetectionMode",
+                    "fineTuneCorners",
+                    "pageDetectionMode",
+                ] {
+                    dependencies_params
+                        .set_attribute(key, params.attribute(key).unwrap_or_default());
+                }
+                if let Some(dependencies) = params.child_mut("dependencies") {
+                    let path = format!("{}/dependencies", path);
+                    self.default_child(
+                        dependencies,
+                        &path,
+                        &["rotated-page-outline"],
+                        dependencies_params,
+                    );
+                }
+            }
+        }
+    }
+
+    fn upgrade_page_layout(&mut self, root: &mut Element) {
+        // pageRect has to agree with select-content, which is upgraded first
+        let page_rects = root
+            .find_all_mut("filters/select-content/page")
+            .into_iter()
+            .filter_map(|page| {
+                let id = page.attribute("id")?.to_owned();
+                let mut rect = page.child("params")?.child("page-rect")?.clone();
+                rect.name = "pageRect".to_owned();
+                Some((id, rect))
+            })
+            .collect::<Vec<_>>();
+
+        for page_layout in root.find_all_mut("filters/page-layout") {
+            let parent = "project/filters/page-layout";
+            self.default_attribute(page_layout, parent, "showMiddleRect", "1");
+
+            for page in page_layout.children_mut("page") {
+                let page_path = page.path(parent);
+                let page_rect = page_rects
+                    .iter()
+                    .find(|(id, _)| Some(id.as_str()) == page.attribute("id"))
+                    .map(|(_, rect)| rect.clone());
+                let Some(params) = page.child_mut("params") else {
+                    continue;
+                };
+                let path = format!("{}/params", page_path);
+
+                self.default_attribute(params, &path, "autoMargins", "0");
+                if let Some(page_rect) = page_rect {
+                    self.default_child(params, &path, &["hardMarginsMM"], page_rect);
+                }
+            }
+        }
+    }
+
+    fn upgrade_output(&mut self, root: &mut Element) {
+        for page in root.find_all_mut("filters/output/page") {
+            let page_path = page.path("project/filters/output");
+
+            // the output snapshot differs between every fork,
+            // ScanTailor rebuilds it on the next output run
+            if let Some(output_params) = page.remove_child("output-params") {
+                self.notes.push(Note::Dropped {
+                    path: page_path.clone(),
+                    field: output_params.name.clone(),
+                    value: output_params.to_string(),
+                });
+            }
+
+            let Some(params) = page.child_mut("params") else {
+                continue;
+            };
+            let path = format!("{}/params", page_path);
+
+            if let Some(level) = params.attribute("despeckleLevel") {
+                let converted = match level {
+                    "off" => Some("0"),
+                    "cautious" => Some("1"),
+                    "normal" => Some("2"),
+                    "aggressive" => Some("3"),
+                    _ => None,
+                };
+                if let Some(converted) = converted {
+                    self.notes.push(Note::Converted {
+                        path: path.clone(),
+                        from: format!("despeckleLevel={}", level),
+                        to: format!("despeckleLevel={}", converted),
+                    });
+                    params.set_attribute("despeckleLevel", converted);
+                }
+            }
+
+            // Experimental kept the dewarping mode on the params themselves
+            if let Some(mode) = params.remove_attribute("dewarpingMode") {
+                let dewarping_options = Element::with_attributes(
+                    "dewarping-options",
+                    &[
+                        ("mode", &mode),
+                        ("postDeskew", "1"),
+                        ("postDeskewAngle", "0"),
+                    ],
+                );
+                self.notes.push(Note::Converted {
+                    path: path.clone(),
+                    from: format!("dewarpingMode={}", mode),
+                    to: dewarping_options.to_string(),
+                });
+                params.insert_child_after(
+                    &["distortion-model", "picture-shape-options"],
+                    dewarping_options,
+                );
+            }
+
+            // and Enhanced the picture shape
+            if let Some(shape) = params.remove_attribute("pictureShape") {
+                let picture_shape_options = Element::with_attributes(
+                    "picture-shape-options",
+                    &[
+                        ("higherSearchSensitivity", "0"),
+                        ("pictureShape", &shape),
+                        ("sensitivity", "100"),
+                    ],
+                );
+                self.notes.push(Note::Converted {
+                    path: path.clone(),
+                    from: format!("pictureShape={}", shape),
+                    to: picture_shape_options.to_string(),
+                });
+                params.insert_child_after(&["distortion-model"], picture_shape_options);
+            }
+
+            if let Some(color_or_grayscale) = params
+                .child_mut("color-params")
+                .and_then(|color_params| color_params.child_mut("color-or-grayscale"))
+            {
+                let path = format!("{}/color-params/color-or-grayscale", path);
+                self.rename_attribute(color_or_grayscale, &path, "whiteMargins", "fillMargins");
+                self.rename_attribute(
+                    color_or_grayscale,
+                    &path,
+                    "normalizeIllumination",
+                    "normalizeIlluminationColor",
+                );
+            }
+
+            self.drop_unknown(
+                params,
+                &path,
+                OUTPUT_PARAMS_ATTRIBUTES,
+                OUTPUT_PARAMS_CHILDREN,
+            );
+        }
+    }
+
+    /// Adds what older builds of every fork, including ScanTailor Advanced, may leave out.
+    fn fill_defaults(&mut self, root: &mut Element) {
+        for page in root.find_all_mut("filters/output/page") {
+            let page_path = page.path("project/filters/output");
+
+            self.default_child(page, &page_path, &[], Element::new("zones"));
+            self.default_child(page, &page_path, &["zones"], Element::new("fill-zones"));
+            self.default_child(page, &page_path, &["params"], processing_params());
+
+            if let Some(params) = page.child_mut("params") {
+                self.fill_output_params(params, &format!("{}/params", page_path));
+            }
+            if let Some(image) = page
+                .child_mut("output-params")
+                .and_then(|output_params| output_params.child_mut("image"))
+            {
+                let path = format!("{}/output-params/image", page_path);
+                self.fill_output_params(image, &path);
+                self.default_child(image, &path, &["dewarping-options"], processing_params());
+            }
+        }
+    }
+
+    fn fill_output_params(&mut self, params: &mut Element, path: &str) {
+        self.default_attribute(params, path, "blackOnWhite", "1");
+        self.default_attribute(params, path, "depthPerception", "2");
+        self.default_attribute(params, path, "despeckleLevel", "1");
+
+        self.default_child(
+            params,
+            path,
+            &["distortion-model"],
+            Element::with_attributes(
+                "picture-shape-options",
+                &[
+                    ("higherSearchSensitivity", "0"),
+                    ("pictureShape", "free"),
+                    ("sensitivity", "100"),
+                ],
+            ),
+        );
+        self.default_child(
+            params,
+            path,
+            &["picture-shape-options"],
+            Element::with_attributes(
+                "dewarping-options",
+                &[
+                    ("mode", "off"),
+                    ("postDeskew", "1"),
+                    ("postDeskewAngle", "0"),
+                ],
+            ),
+        );
+        self.default_child(
+            params,
+            path,
+            &["color-params"],
+            Element::with_attributes(
+                "splitting",
+                &[
+                    ("originalBackground", "0"),
+                    ("splitOutput", "0"),
+                    ("splittingMode", "bw"),
+                ],
+            ),
+        );
+
+        let Some(color_params) = params.child_mut("color-params") else {
+            return;
+        };
+        let path = format!("{}/color-params", path);
+
+        if let Some(color_or_grayscale) = color_params.child_mut("color-or-grayscale") {
+            let path = format!("{}/color-or-grayscale", path);
+            self.default_attribute(color_or_grayscale, &path, "fillMargins", "1");
+            self.default_attribute(color_or_grayscale, &path, "fillOffcut", "1");
+            self.default_attribute(color_or_grayscale, &path, "fillingColor", "background");
+            self.default_attribute(color_or_grayscale, &path, "normalizeIlluminationColor", "0");
+            self.default_child(
+                color_or_grayscale,
+                &path,
+                &[],
+                Element::with_attributes(
+                    "posterization-options",
+                    &[
+                        ("enabled", "0"),
+                        ("forceBlackAndWhite", "1"),
+                        ("level", "4"),
+                        ("normalizationEnabled", "0"),
+                    ],
+                ),
+            );
+        }
+
+        if let Some(bw) = color_params.child_mut("bw") {
+            let path = format!("{}/bw", path);
+            for (key, value) in [
+                ("binarizationMethod", "otsu"),
+                ("morphologicalSmoothing", "1"),
+                ("normalizeIlluminationBW", "1"),
+                ("sauvolaCoef", "0.34"),
+                ("savitzkyGolaySmoothing", "1"),
+                ("thresholdAdj", "0"),
+                ("windowSize", "200"),
+                ("wolfCoef", "0.3"),
+                ("wolfLowerBound", "1"),
+                ("wolfUpperBound", "254"),
+            ] {
+                self.default_attribute(bw, &path, key, value);
+            }
+            self.default_child(
+                bw,
+                &path,
+                &[],
+                Element::with_attributes(
+                    "color-segmenter-options",
+                    &[
+                        ("blueThresholdAdjustment", "0"),
+                        ("enabled", "0"),
+                        ("greenThresholdAdjustment", "0"),
+                        ("noiseReduction", "7"),
+                        ("redThresholdAdjustment", "0"),
+                    ],
+                ),
+            );
+        }
+    }
+}
+
+fn processing_params() -> Element {
+    Element::with_attributes(
+        "processing-params",
+        &[("autoZonesFound", "0"), ("blackOnWhiteSetManually", "0")],
+    )
+}
+
+fn bounding_rect(outline: &Element) -> Option<Element> {
+    let points = outline
+        .elements()
+        .map(|point| {
+            let x = point.attribute("x")?.parse::<f64>().ok()?;
+            let y = point.attribute("y")?.parse::<f64>().ok()?;
+            Some((x, y))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    if points.is_empty() {
+        return None;
+    }
+
+    let (mut left, mut top) = points[0];
+    let (mut right, mut bottom) = points[0];
+    for (x, y) in points {
+        left = left.min(x);
+        top = top.min(y);
+        right = right.max(x);
+        bottom = bottom.max(y);
+    }
+
+    Some(Element::with_attributes(
+        "page-rect",
+        &[
+            ("height", &format_double(bottom - top)),
+            ("width", &format_double(right - left)),
+            ("x", &format_double(left)),
+            ("y", &format_double(top)),
+        ],
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::se::to_string;
+    use validator::Validate;
+
+    use crate::{error::ParseError, project::test::ORIGINAL_CONTENT, units::Px, xml::Element};
+
+    use super::{migrate, Dialect, Note, CURRENT_VERSION};
+
+    const SCANTAILOR: &str = r#"<project outputDirectory="/home/ramzi/src/bill-organizer/out" version="2"><directories><directory id="1" path="/home/ramzi/src/bill-organizer"/></directories><files><file dirId="1" id="2" name="in.jpg"/></files><images><image fileId="2" fileImage="0" id="3" subPages="1"><size height="4961" width="7016"/><dpi horizontal="600" vertical="600"/></image></images><pages><page id="4" imageId="3" selected="selected" subPage="single"/></pages><file-name-disambiguation><mapping file="2" label="0"/></file-name-disambiguation><filters><fix-orientation/><page-split defaultLayoutType="auto-detect"><image id="3" layoutType="single-uncut"><params mode="auto"><pages type="single-uncut"><outline><point x="0" y="0"/><point x="7016" y="0"/><point x="7016" y="4961"/><point x="0" y="4961"/><point x="0" y="0"/></outline></pages><dependencies><rotation degrees="0"/><size height="4961" width="7016"/><layoutType>single-uncut</layoutType></dependencies></params></image></page-split><deskew><page id="4"><params angle="0.125" mode="auto"><dependencies><rotation degrees="0"/><page-outline><point x="0" y="0"/><point x="7016" y="0"/><point x="7016" y="4961"/><point x="0" y="4961"/><point x="0" y="0"/></page-outline></dependencies></params></page></deskew><select-content><page id="4"><params mode="auto"><content-rect height="3384" width="2260" x="344" y="388"/><content-size-mm height="143.256286512573" width="95.6735246803827"/><dependencies><rotated-page-outline><point x="10.5" y="0"/><point x="7026.5" y="15.5"/><point x="7015.5" y="4976.5"/><point x="0" y="4960.5"/><point x="10.5" y="0"/></rotated-page-outline></dependencies></params></page></select-content><page-layout><page id="4"><params><hardMarginsMM bottom="5" left="10" right="10" top="5"/><contentRect height="3384" width="2260" x="344" y="388"/><contentSizeMM height="143.256286512573" width="95.6735246803827"/><alignment hor="center" null="0" vert="center"/></params></page></page-layout><output><page id="4"><zones/><fill-zones/><params despeckleLevel="cautious" thresholdAdj="5"><dpi horizontal="600" vertical="600"/><color-params colorMode="bw"><color-or-grayscale normalizeIllumination="1" whiteMargins="1"/><bw/></color-params></params><output-params><image despeckleLevel="cautious"><size height="3620" width="2732"/></image></output-params></page></output></filters></project>"#;
+
+    #[test]
+    fn it_migrates_scantailor_projects() {
+        let migrated = migrate(SCANTAILOR).unwrap();
+        assert_eq!(migrated.dialect, Dialect::ScanTailor);
+        assert_eq!(migrated.version, 2);
+        assert_eq!(migrated.project.version, CURRENT_VERSION);
+        migrated.project.validate().unwrap();
+
+        let select_content = &migrated
+            .project
+            .filters
+            .select_content
+            .page
+            .as_ref()
+            .unwrap()[0];
+        assert_eq!(select_content.params.page_rect.width, Px(7026.5));
+        assert_eq!(select_content.params.page_rect.height, Px(4976.5));
+
+        let output = &migrated.project.filters.output.page[0];
+        assert_eq!(output.params.despeckle_level, 1f64);
+        assert!(output.output_params.is_none());
+
+        assert!(migrated.notes.contains(&Note::Converted {
+            path: "project/filters/output/page[id=4]/params/color-params/color-or-grayscale"
+                .to_owned(),
+            from: "whiteMargins".to_owned(),
+            to: "fillMargins".to_owned(),
+        }));
+        assert!(migrated.notes.contains(&Note::Dropped {
+            path: "project/filters/output/page[id=4]/params".to_owned(),
+            field: "thresholdAdj".to_owned(),
+            value: "5".to_owned(),
+        }));
+        assert!(migrated.notes.contains(&Note::Defaulted {
+            path: "project/filters/select-content/page[id=4]/params".to_owned(),
+            field: "pageDetectionMode".to_owned(),
+            value: "disabled".to_owned(),
+        }));
+    }
+
+    #[test]
+    fn it_migrates_forks() {
+        let experimental = SCANTAILOR.replace(
+            r#"<params despeckleLevel="cautious" thresholdAdj="5">"#,
+            r#"<params dewarpingMode="manual" despeckleLevel="cautious">"#,
+        );
+        let migrated = migrate(&experimental).unwrap();
+        assert_eq!(migrated.dialect, Dialect::Experimental);
+        migrated.project.validate().unwrap();
+
+        let enhanced = SCANTAILOR.replace(
+            r#"<params despeckleLevel="cautious" thresholdAdj="5">"#,
+            r#"<params despeckleLevel="cautious" pictureShape="rectangular">"#,
+        );
+        let migrated = migrate(&enhanced).unwrap();
+        assert_eq!(migrated.dialect, Dialect::Enhanced);
+        migrated.project.validate().unwrap();
+    }
+
+    #[test]
+    fn it_fills_in_missing_color_segmenter_options() {
+        let content = ORIGINAL_CONTENT.replace(
+            r#"<color-segmenter-options blueThresholdAdjustment="0" enabled="0" greenThresholdAdjustment="0" noiseReduction="7" redThresholdAdjustment="0"/>"#,
+            "",
+        );
+        let migrated = migrate(&content).unwrap();
+        assert_eq!(migrated.dialect, Dialect::Advanced);
+        assert_eq!(migrated.notes.len(), 4);
+        assert_eq!(to_string(&migrated.project).unwrap(), ORIGINAL_CONTENT);
+    }
+
+    #[test]
+    fn it_detects_scantailor_advanced_from_version_3() {
+        let detect = |content: &str| Dialect::detect(&mut Element::parse(content).unwrap());
+
+        assert_eq!(detect(ORIGINAL_CONTENT), Dialect::Advanced);
+        assert_eq!(
+            detect(&ORIGINAL_CONTENT.replacen(r#"version="3""#, r#"version="4""#, 1)),
+            Dialect::Advanced
+        );
+        // the same layout, but a version only the older forks write
+        assert_eq!(
+            detect(&ORIGINAL_CONTENT.replacen(r#"version="3""#, r#"version="2""#, 1)),
+            Dialect::ScanTailor
+        );
+        assert_eq!(
+            detect(&SCANTAILOR.replacen(r#"version="2""#, r#"version="3""#, 1)),
+            Dialect::Advanced
+        );
+        assert_eq!(
+            detect(&SCANTAILOR.replacen(r#" version="2""#, "", 1)),
+            Dialect::ScanTailor
+        );
+    }
+
+    #[test]
+    fn it_refuses_newer_versions() {
+        let content = ORIGINAL_CONTENT.replacen(r#"version="3""#, r#"version="4""#, 1);
+        assert_eq!(
+            migrate(&content).unwrap_err().to_string(),
+            "project version 4 is newer than 3, the latest this crate can migrate"
+        );
+
+        let content = ORIGINAL_CONTENT.replacen(r#"version="3""#, r#"version="300""#, 1);
+        assert_eq!(
+            migrate(&content).unwrap_err().to_string(),
+            "invalid project version \"300\""
+        );
+    }
+
+    #[test]
+    fn it_locates_errors_in_the_original_content() {
+        let content =
+            SCANTAILOR
+                .replace("><", ">\n<")
+                .replacen(r#"angle="0.125""#, r#"angle="tilted""#, 1);
+        let error = migrate(&content).unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(error.path, "project/filters/deskew/page[id=4]/params");
+        assert_eq!(
+            content.lines().nth(error.line - 1).unwrap(),
+            r#"<params angle="tilted" mode="auto">"#
+        );
+
+        let content = SCANTAILOR.replacen("</deskew>", "", 1);
+        let error = migrate(&content).unwrap_err();
+        let error = error.downcast_ref::<ParseError>().unwrap();
+        assert_eq!(error.path, "project/filters/deskew");
+        assert!(content[error.offset..].starts_with("<deskew>"));
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display},
+    hash::Hash,
+};
+
+use crate::{
+    filters::{
+        output::{
+            common::{ColorParams, DewarpingMode, DistortionModel},
+            output_params::File,
+        },
+        EntryVisitor,
+    },
+    id::{ImageId, PageId},
+};
+
+use super::ProjectXML;
+
+/// A repair made by [`normalize`].
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Fix {
+    /// a distortion model ScanTailor left behind after dewarping was turned off
+    StaleDistortionModel { path: String },
+    /// black and white settings of a page output in color or grayscale
+    InactiveBlackAndWhite { path: String },
+    /// an output file ScanTailor does not write with the current settings
+    StaleFile { path: String, file: &'static str },
+    /// an entry for a page or image that is no longer part of the project
+    DeletedEntry { path: String },
+}
+
+impl Display for Fix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Fix::StaleDistortionModel { path } => write!(
+                f,
+                "{}: removed distortion-model left behind with dewarping off",
+                path
+            ),
+            Fix::InactiveBlackAndWhite { path } => write!(
+                f,
+                "{}: removed bw settings of a page in color or grayscale",
+                path
+            ),
+            Fix::StaleFile { path, file } => write!(
+                f,
+                "{}: removed {} file not written with the current settings",
+                path, file
+            ),
+            Fix::DeletedEntry { path } => {
+                write!(f, "{}: removed entry no longer part of the project", path)
+            }
+        }
+    }
+}
+
+/// What to do with settings the current mode of a page does not use, which ScanTailor
+/// keeps to restore them when the mode is switched back.
+#[derive(Clone, Copy, Default, Eq, PartialEq, Debug)]
+pub enum InactiveSettings {
+    #[default]
+    Keep,
+    /// remove them, as the black and white settings of pages in color or grayscale
+    Remove,
+}
+
+/// Repairs the leftovers ScanTailor is known to leave behind, where what was meant is
+/// unambiguous, returning what was changed. Nothing that is still in use is ever removed,
+/// and settings kept for another mode only when asked.
+pub fn normalize(project: &mut ProjectXML, inactive: InactiveSettings) -> Vec<Fix> {
+    let mut fixes = vec![];
+
+    let pages = project
+        .pages
+        .page
+        .iter()
+        .map(|page| page.id)
+        .collect::<HashSet<_>>();
+    let images = project
+        .images
+        .image
+        .iter()
+        .map(|image| image.id)
+        .collect::<HashSet<_>>();
+    project.filters.visit_entries(&mut Retain {
+        pages: &pages,
+        images: &images,
+        fixes: &mut fixes,
+    });
+
+    let filters = &mut project.filters;
+    for page in filters.output.page.iter_mut() {
+        let path = format!("project/filters/output/page[id={}]", page.id);
+
+        let params = &mut page.params;
+        let path_params = format!("{}/params", path);
+        remove_stale_distortion_model(
+            &mut params.distortion_model,
+            &params.dewarping_options.mode,
+            &path_params,
+            &mut fixes,
+        );
+        if inactive == InactiveSettings::Remove {
+            remove_inactive_bw(&mut params.color_params, &path_params, &mut fixes);
+        }
+
+        let Some(output) = page.output_params.as_mut() else {
+            continue;
+        };
+        let path = format!("{}/output-params", path);
+        let image = &mut output.image;
+        let path_image = format!("{}/image", path);
+        remove_stale_distortion_model(
+            &mut image.distortion_model,
+            &image.dewarping_options.mode,
+            &path_image,
+            &mut fixes,
+        );
+        if inactive == InactiveSettings::Remove {
+            remove_inactive_bw(&mut image.color_params, &path_image, &mut fixes);
+        }
+
+        let split = output.expects_split_files();
+        let automask = output.expects_automask();
+        let speckles = output.expects_speckles();
+        for (expected, file, name) in [
+            (split, &mut output.foreground_file, "foreground_file"),
+            (split, &mut output.background_file, "background_file"),
+            (automask, &mut output.automask, "automask"),
+            (speckles, &mut output.speckles, "speckles"),
+        ] {
+            remove_stale_file(expected, file, name, &path, &mut fixes);
+        }
+    }
+
+    fixes
+}
+
+/// Removes the entries of pages and images the project no longer has.
+struct Retain<'a> {
+    pages: &'a HashSet<PageId>,
+    images: &'a HashSet<ImageId>,
+    fixes: &'a mut Vec<Fix>,
+}
+
+impl EntryVisitor for Retain<'_> {
+    fn images<T>(&mut self, entries: Option<&mut Vec<T>>, id: fn(&T) -> ImageId, path: &str) {
+        retain(entries, id, self.images, path, self.fixes);
+    }
+
+    fn pages<T>(&mut self, entries: Option<&mut Vec<T>>, id: fn(&T) -> PageId, path: &str) {
+        retain(entries, id, self.pages, path, self.fixes);
+    }
+}
+
+fn retain<T, I: Eq + Hash + Display>(
+    entries: Option<&mut Vec<T>>,
+    id: fn(&T) -> I,
+    known: &HashSet<I>,
+    path: &str,
+    fixes: &mut Vec<Fix>,
+) {
+    let Some(entries) = entries else {
+        return;
+    };
+    entries.retain(|entry| {
+        let kept = known.contains(&id(entry));
+        if !kept {
+            fixes.push(Fix::DeletedEntry {
+                path: format!("{}[id={}]", path, id(entry)),
+            });
+        }
+        kept
+    });
+}
+
+fn remove_stale_distortion_model(
+    model: &mut Option<DistortionModel>,
+    mode: &DewarpingMode,
+    path: &str,
+    fixes: &mut Vec<Fix>,
+) {
+    // manual dewarping is done with the stored model
+    if model.is_some() && *mode == DewarpingMode::Off {
+        *model = None;
+        fixes.push(Fix::StaleDistortionModel {
+            path: path.to_owned(),
+        });
+    }
+}
+
+fn remove_inactive_bw(color_params: &mut ColorParams, path: &str, fixes: &mut Vec<Fix>) {
+    let ColorParams::ColorOrGrayscale(params) = color_params else {
+        return;
+    };
+    if params.bw.take().is_some() {
+        fixes.push(Fix::InactiveBlackAndWhite {
+            path: format!("{}/color-params", path),
+        });
+    }
+}
+
+fn remove_stale_file(
+    expected: bool,
+    file: &mut Option<File>,
+    name: &'static str,
+    path: &str,
+    fixes: &mut Vec<Fix>,
+) {
+    if !expected && file.is_some() {
+        *file = None;
+        fixes.push(Fix::StaleFile {
+            path: path.to_owned(),
+            file: name,
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::de::from_str;
+    use validator::Validate;
+
+    use crate::{project::test::ORIGINAL_CONTENT, ProjectXML};
+
+    use super::{normalize, InactiveSettings};
+
+    #[test]
+    fn it_repairs_leftovers() {
+        let content = ORIGINAL_CONTENT
+            .replacen(
+                r#"<picture-shape-options"#,
+                r#"<distortion-model><top-curve><polyline>4QKnQ9eiwUNGICJFuyu/Qw==</polyline></top-curve><bottom-curve><polyline>3rOqQzq0a0VmliJFVmVrRQ==</polyline></bottom-curve></distortion-model><picture-shape-options"#,
+                1,
+            )
+            .replacen(
+                r#"<file mtime="1699247251" size="12520108"/>"#,
+                r#"<file mtime="1699247251" size="12520108"/><speckles mtime="1699247251" size="712"/>"#,
+                1,
+            )
+            .replacen(
+                r#"<image-settings><page id="4"><image-params blackOnWhite="1" bwThreshold="190"/>"#,
+                r#"<page id="9"><params angle="0.5" mode="auto"><dependencies><rotation degrees="90"/><page-outline><point x="0" y="0"/><point x="1" y="0"/><point x="1" y="1"/><point x="0" y="1"/><point x="0" y="0"/></page-outline></dependencies></params></page><image-settings><page id="4"><image-params blackOnWhite="1" bwThreshold="190"/>"#,
+                1,
+            );
+        let mut project: ProjectXML = from_str(&content).unwrap();
+        assert!(project.validate().is_err());
+
+        let fixes = normalize(&mut project, InactiveSettings::Remove);
+        assert_eq!(
+            fixes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            vec![
+                "project/filters/deskew/page[id=9]: removed entry no longer part of the project",
+                "project/filters/output/page[id=4]/params: removed distortion-model left behind with dewarping off",
+                "project/filters/output/page[id=4]/params/color-params: removed bw settings of a page in color or grayscale",
+                "project/filters/output/page[id=4]/output-params/image/color-params: removed bw settings of a page in color or grayscale",
+                "project/filters/output/page[id=7]/params/color-params: removed bw settings of a page in color or grayscale",
+                "project/filters/output/page[id=7]/output-params/image/color-params: removed bw settings of a page in color or grayscale",
+                "project/filters/output/page[id=7]/output-params: removed speckles file not written with the current settings",
+            ]
+        );
+        project.validate().unwrap();
+        assert!(normalize(&mut project, InactiveSettings::Remove).is_empty());
+    }
+
+    #[test]
+    fn it_keeps_what_is_still_in_use() {
+        let mut project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        assert!(normalize(&mut project, InactiveSettings::default()).is_empty());
+
+        let content = ORIGINAL_CONTENT
+            .replacen(
+                r#"<picture-shape-options"#,
+                r#"<distortion-model><top-curve><polyline>4QKnQ9eiwUNGICJFuyu/Qw==</polyline></top-curve><bottom-curve><polyline>3rOqQzq0a0VmliJFVmVrRQ==</polyline></bottom-curve></distortion-model><picture-shape-options"#,
+                1,
+            )
+            .replacen(r#"<dewarping-options mode="off""#, r#"<dewarping-options mode="manual""#, 1);
+        let mut project: ProjectXML = from_str(&content).unwrap();
+        assert!(normalize(&mut project, InactiveSettings::default()).is_empty());
+        assert!(project.filters.output.page[0]
+            .params
+            .distortion_model
+            .is_some());
+        project.validate().unwrap();
+    }
+}
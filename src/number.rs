@@ -0,0 +1,127 @@
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_with::{DeserializeAs, SerializeAs};
+
+/// Length of the exponent Qt writes, as in `1e-05`, counting the `e` but not the sign.
+const EXPONENT_LENGTH: i32 = 4;
+
+/// Formats a double the way ScanTailor writes it, with
+/// `QString::number(value, 'g', QLocale::FloatingPointShortest)`,
+/// so that an unmodified project is written back byte for byte.
+///
+/// That is the shortest digits reading back to the same double, written in decimal
+/// unless the zeros this takes are longer than an exponent would be.
+pub fn format_double(value: f64) -> String {
+    if value.is_nan() {
+        return "nan".to_owned();
+    }
+    if value.is_infinite() {
+        return if value < 0f64 { "-inf" } else { "inf" }.to_owned();
+    }
+
+    // Rust's exponent formatting also writes the shortest round-tripping digits
+    let scientific = format!("{:e}", value);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let sign = if mantissa.starts_with('-') { "-" } else { "" };
+    let digits = mantissa
+        .chars()
+        .filter(char::is_ascii_digit)
+        .collect::<String>();
+
+    // position of the decimal point relative to the first digit
+    let point = exponent + 1;
+    let count = digits.len() as i32;
+    let decimal = if point <= 0 {
+        1 - point <= EXPONENT_LENGTH
+    } else {
+        point <= count || point - count <= EXPONENT_LENGTH
+    };
+
+    if !decimal {
+        let (first, rest) = digits.split_at(1);
+        format!(
+            "{}{}{}{}e{}{:02}",
+            sign,
+            first,
+            if rest.is_empty() { "" } else { "." },
+            rest,
+            if exponent < 0 { '-' } else { '+' },
+            exponent.abs()
+        )
+    } else if point <= 0 {
+        format!("{}0.{}{}", sign, "0".repeat(-point as usize), digits)
+    } else if point >= count {
+        format!("{}{}{}", sign, digits, "0".repeat((point - count) as usize))
+    } else {
+        let (integral, fractional) = digits.split_at(point as usize);
+        format!("{}{}.{}", sign, integral, fractional)
+    }
+}
+
+/// Serializes numbers the way ScanTailor writes them, for use with `serde_as`.
+pub struct QtNumber;
+
+impl SerializeAs<f64> for QtNumber {
+    fn serialize_as<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&format_double(*value))
+    }
+}
+
+impl<'de> DeserializeAs<'de, f64> for QtNumber {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        f64::deserialize(deserializer)
+    }
+}
+
+impl SerializeAs<u32> for QtNumber {
+    fn serialize_as<S: Serializer>(value: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(*value)
+    }
+}
+
+impl<'de> DeserializeAs<'de, u32> for QtNumber {
+    fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        u32::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::format_double;
+
+    #[test]
+    fn it_formats_doubles_like_qt() {
+        for (value, expected) in [
+            (0f64, "0"),
+            (-0f64, "-0"),
+            (1f64, "1"),
+            (0.1 + 0.2, "0.30000000000000004"),
+            (1e-5, "1e-05"),
+            (0.0001, "0.0001"),
+            (-1.5e-7, "-1.5e-07"),
+            (1e16, "1e+16"),
+            (10000f64, "10000"),
+            (100000f64, "1e+05"),
+            (1.5e15, "1.5e+15"),
+            (9.99999999999999999, "10"),
+            // as found in projects written by ScanTailor Advanced
+            (0.34, "0.34"),
+            (0.125, "0.125"),
+            (4960f64, "4960"),
+            (4959.99008, "4959.99008"),
+            (8.31212396882309, "8.31212396882309"),
+            (8.32739558766345, "8.32739558766345"),
+            (3809.99093287571, "3809.99093287571"),
+            (7015.9833031643, "7015.9833031643"),
+            (1142.578008915305, "1142.578008915305"),
+            (7015.985967999999, "7015.985967999999"),
+            (-235.6726044123365, "-235.6726044123365"),
+            (0.9999976201773518, "0.9999976201773518"),
+            (-0.00218165983433677, "-0.00218165983433677"),
+            (f64::NAN, "nan"),
+            (f64::NEG_INFINITY, "-inf"),
+        ] {
+            assert_eq!(format_double(value), expected);
+        }
+    }
+}
@@ -1,30 +1,30 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+use super::id::{ImageId, PageId};
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Pages {
     pub page: Vec<Page>,
 }
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 #[serde(deny_unknown_fields)]
 pub struct Page {
-    #[serde(rename(serialize = "@id"))]
-    pub id: u32,
+    #[serde(rename = "@id")]
+    pub id: PageId,
 
-    #[serde(rename(serialize = "@imageId", deserialize = "imageId"))]
-    pub image_id: u32,
+    #[serde(rename = "@imageId")]
+    pub image_id: ImageId,
 
-    #[serde(
-        rename(serialize = "@selected"),
-        skip_serializing_if = "Option::is_none"
-    )]
+    #[serde(rename = "@selected", skip_serializing_if = "Option::is_none")]
     pub selected: Option<Selected>,
 
-    #[serde(rename(serialize = "@subPage", deserialize = "subPage"))]
+    #[serde(rename = "@subPage")]
     pub sub_page: SubPage,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Clone, Copy, Eq, PartialEq, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum SubPage {
     Single,
@@ -32,7 +32,7 @@ pub enum SubPage {
     Right,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, JsonSchema, Debug)]
 pub enum Selected {
     #[serde(rename = "selected")]
     Selected,
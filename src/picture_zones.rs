@@ -0,0 +1,235 @@
+use anyhow::{anyhow, Result};
+
+use crate::{
+    common::Point,
+    filters::{
+        common::{FilledOutline, Outline, Rect},
+        output::common::zones::{
+            Layer, PictureZoneProperty, Properties, Property, Zone, ZoneCategory,
+            ZoneCategoryProperty,
+        },
+    },
+    id::PageId,
+    mapping::PageTransform,
+    ProjectXML,
+};
+
+/// Describes a picture zone drawn on the output image of a page, to store it the way
+/// ScanTailor does, on the source image.
+#[derive(Clone, Debug)]
+pub struct ZoneBuilder {
+    polygon: Vec<Point>,
+    layer: Layer,
+    category: ZoneCategory,
+    rendered: bool,
+}
+
+impl ZoneBuilder {
+    /// A polygon painted as a picture, as if drawn by hand.
+    pub fn polygon(polygon: Vec<Point>) -> Self {
+        ZoneBuilder {
+            polygon,
+            layer: Layer::Painter2,
+            category: ZoneCategory::Manual,
+            rendered: false,
+        }
+    }
+
+    /// A rectangle painted as a picture, as if drawn by hand.
+    pub fn rect(rect: &Rect) -> Self {
+        // ScanTailor goes around rectangles the other way
+        let [top_left, top_right, bottom_right, bottom_left] = rect.corners();
+        ZoneBuilder {
+            category: ZoneCategory::RectangularOutline,
+            ..ZoneBuilder::polygon(vec![top_left, bottom_left, bottom_right, top_right])
+        }
+    }
+
+    pub fn layer(self, layer: Layer) -> Self {
+        ZoneBuilder { layer, ..self }
+    }
+
+    pub fn category(self, category: ZoneCategory) -> Self {
+        ZoneBuilder { category, ..self }
+    }
+
+    /// Records the zone with the output params as well, if any, for when the output image
+    /// already accounts for it. Otherwise ScanTailor sees the zones changed and
+    /// generates the output again.
+    pub fn rendered(self) -> Self {
+        ZoneBuilder {
+            rendered: true,
+            ..self
+        }
+    }
+
+    /// The zone as ScanTailor stores it.
+    pub fn build(&self, transform: &PageTransform) -> Result<Zone> {
+        let mut polygon = self.polygon.clone();
+        polygon.dedup();
+        if polygon.first() == polygon.last() {
+            polygon.pop();
+        }
+        if polygon.len() < 3 {
+            return Err(anyhow!(
+                "a zone needs 3 corners, got {} on page {}",
+                polygon.len(),
+                transform.page
+            ));
+        }
+        let to_source = transform.to_source();
+        let polygon = polygon
+            .iter()
+            .map(|point| to_source.map_point(point))
+            .collect();
+        Ok(Zone {
+            spline: Outline::Filled(FilledOutline::from_polygon(polygon)),
+            properties: Properties {
+                property: vec![
+                    Property::ZoneCategory(ZoneCategoryProperty {
+                        category: self.category,
+                    }),
+                    Property::PictureZone(PictureZoneProperty { layer: self.layer }),
+                ],
+            },
+        })
+    }
+
+    /// Adds the zone to a page.
+    ///
+    /// Zones found by picture detection tell ScanTailor it need not look for them
+    /// again, which it otherwise does when generating the output.
+    pub fn insert(&self, project: &mut ProjectXML, page_id: PageId) -> Result<()> {
+        let zone = self.build(&PageTransform::new(project, page_id)?)?;
+        let page = project
+            .filters
+            .output
+            .page
+            .iter_mut()
+            .find(|page| page.id == page_id)
+            .ok_or_else(|| anyhow!("page {} has no output params", page_id))?;
+        let auto = self.category == ZoneCategory::Auto;
+
+        if self.rendered {
+            if let Some(output_params) = &mut page.output_params {
+                output_params.zones.push(zone.clone());
+                if auto {
+                    output_params.image.processing_params.auto_zones_found = 1;
+                }
+            }
+        }
+        page.zones.push(zone);
+        if auto {
+            page.processing_params.auto_zones_found = 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::{de::from_str, se::to_string};
+    use validator::Validate;
+
+    use crate::{
+        common::Point,
+        filters::{
+            common::{Outline, Rect},
+            output::common::zones::{Layer, ZoneCategory},
+        },
+        id::PageId,
+        mapping::PageTransform,
+        project::test::{ORIGINAL_CONTENT, ZONED_CONTENT},
+        units::Px,
+        ProjectXML,
+    };
+
+    use super::ZoneBuilder;
+
+    #[test]
+    fn it_draws_zones_like_scantailor() {
+        let project: ProjectXML = from_str(ZONED_CONTENT).unwrap();
+        let transform = PageTransform::new(&project, PageId(4)).unwrap();
+
+        // the zones picture detection found, on whole pixels of the output
+        let page = &project.filters.output.page[0];
+        for (zone, rect) in page.zones.zones().iter().zip([
+            Rect::new(Px(1198f64), Px(120f64), Px(694f64), Px(3328f64)),
+            Rect::new(Px(566f64), Px(1402f64), Px(278f64), Px(876f64)),
+        ]) {
+            let built = ZoneBuilder::rect(&rect)
+                .category(ZoneCategory::Auto)
+                .build(&transform)
+                .unwrap();
+            assert_eq!(built.layer(), zone.layer());
+            assert_eq!(built.category(), zone.category());
+            let (Outline::Filled(built), Outline::Filled(stored)) = (&built.spline, &zone.spline)
+            else {
+                panic!();
+            };
+            assert_eq!(built.point.len(), stored.point.len());
+            for (a, b) in built.point.iter().zip(&stored.point) {
+                assert!(a.distance(b) < Px(1e-6), "{:?} != {:?}", a, b);
+            }
+        }
+
+        let line = ZoneBuilder::polygon(vec![
+            Point::new(Px(0f64), Px(0f64)),
+            Point::new(Px(10f64), Px(10f64)),
+            Point::new(Px(0f64), Px(0f64)),
+        ]);
+        assert!(line.build(&transform).is_err());
+    }
+
+    #[test]
+    fn it_inserts_zones() {
+        let mut project: ProjectXML = from_str(ZONED_CONTENT).unwrap();
+        let page = &mut project.filters.output.page[0];
+        let output_params = page.output_params.as_mut().unwrap();
+        page.zones.zone = None;
+        page.processing_params.auto_zones_found = 0;
+        output_params.zones.zone = None;
+        output_params.image.processing_params.auto_zones_found = 0;
+
+        // a logo, left to be picked up by picture detection
+        let logo = ZoneBuilder::polygon(vec![
+            Point::new(Px(300f64), Px(200f64)),
+            Point::new(Px(500f64), Px(200f64)),
+            Point::new(Px(400f64), Px(300f64)),
+        ]);
+        logo.insert(&mut project, PageId(4)).unwrap();
+        // a QR code, already preserved in the output
+        ZoneBuilder::rect(&Rect::new(Px(2000f64), Px(3000f64), Px(300f64), Px(300f64)))
+            .category(ZoneCategory::Auto)
+            .rendered()
+            .insert(&mut project, PageId(4))
+            .unwrap();
+
+        let page = &project.filters.output.page[0];
+        let kinds = page
+            .zones
+            .zones()
+            .iter()
+            .map(|zone| (zone.layer(), zone.category()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            kinds,
+            vec![
+                (Some(Layer::Painter2), Some(ZoneCategory::Manual)),
+                (Some(Layer::Painter2), Some(ZoneCategory::Auto)),
+            ]
+        );
+        assert_eq!(page.processing_params.auto_zones_found, 1);
+        let output_params = page.output_params.as_ref().unwrap();
+        assert_eq!(output_params.zones.zones().len(), 1);
+        assert_eq!(output_params.image.processing_params.auto_zones_found, 1);
+
+        let written: ProjectXML = from_str(&to_string(&project).unwrap()).unwrap();
+        written.validate().unwrap();
+        assert_eq!(written.filters.output.page[0].zones.zones().len(), 2);
+
+        // pages ScanTailor has not laid out have no output to draw on
+        let mut project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        assert!(logo.insert(&mut project, PageId(5)).is_err());
+    }
+}
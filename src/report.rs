@@ -0,0 +1,330 @@
+use std::{
+    borrow::Cow,
+    fmt::{self, Display},
+};
+
+use serde::Serialize;
+use serde_json::Value;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+
+use crate::id::{ImageId, PageId};
+
+use super::ProjectXML;
+
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Serialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        })
+    }
+}
+
+/// One problem found in a project.
+#[derive(Clone, Eq, PartialEq, Serialize, Debug)]
+pub struct Problem {
+    /// stable identifier of the kind of problem, as in `layout_type_mismatch`
+    pub code: Cow<'static, str>,
+    pub severity: Severity,
+    /// id of the page the problem was found on, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageId>,
+    /// id of the image the problem was found on, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<ImageId>,
+    /// path of the element or attribute, as in `project/filters/page-split/image[id=14]/params/pages`
+    /// or `project/filters/deskew/page[id=7]/params/@angle`
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}]: {}: {}",
+            self.severity, self.code, self.path, self.message
+        )
+    }
+}
+
+/// Every problem found in a project, one per line when displayed.
+#[derive(Clone, Default, Eq, PartialEq, Serialize, Debug)]
+pub struct Report {
+    pub problems: Vec<Problem>,
+}
+
+impl Report {
+    pub fn is_empty(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.problems
+            .iter()
+            .any(|problem| problem.severity == Severity::Error)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in self.problems.iter() {
+            writeln!(f, "{}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Validates a project, flattening what [`Validate`] finds into a [`Report`].
+pub fn validate(project: &ProjectXML) -> Report {
+    let errors = project.validate().err().unwrap_or_default();
+    let mut found = vec![];
+    flatten(&errors, &mut vec![], &mut found);
+    found.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    Report {
+        problems: found
+            .into_iter()
+            .map(|(segments, error)| {
+                let (path, page, image) = locate(project, &segments);
+                Problem {
+                    code: error.code.clone(),
+                    severity: Severity::Error,
+                    page,
+                    image,
+                    path,
+                    message: message(error),
+                }
+            })
+            .collect(),
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
+enum Segment {
+    Field(&'static str),
+    Index(usize),
+}
+
+fn flatten<'a>(
+    errors: &'a ValidationErrors,
+    segments: &mut Vec<Segment>,
+    found: &mut Vec<(Vec<Segment>, &'a ValidationError)>,
+) {
+    for (field, kind) in errors.errors() {
+        // errors of a whole struct are reported on the struct itself
+        if *field != "__all__" {
+            segments.push(Segment::Field(field));
+        }
+        match kind {
+            ValidationErrorsKind::Struct(errors) => flatten(errors, segments, found),
+            ValidationErrorsKind::List(list) => {
+                for (index, errors) in list {
+                    segments.push(Segment::Index(*index));
+                    flatten(errors, segments, found);
+                    segments.pop();
+                }
+            }
+            ValidationErrorsKind::Field(errors) => {
+                found.extend(errors.iter().map(|error| (segments.clone(), error)))
+            }
+        }
+        if *field != "__all__" {
+            segments.pop();
+        }
+    }
+}
+
+/// Where the segments lead, as an element path with the ids of entries, along with
+/// the page and image ids of the innermost entry.
+fn locate(project: &ProjectXML, segments: &[Segment]) -> (String, Option<PageId>, Option<ImageId>) {
+    let filters = &project.filters;
+    let mut path = "project".to_owned();
+    let (mut page, mut image) = (None, None);
+    for segment in segments {
+        let index = match *segment {
+            Segment::Field(field) => {
+                path.push('/');
+                path.push_str(field);
+                continue;
+            }
+            Segment::Index(index) => index,
+        };
+        let ids = match path.as_str() {
+            "project/images/image" => (None, project.images.image.get(index).map(|image| image.id)),
+            "project/pages/page" => (project.pages.page.get(index).map(|page| page.id), None),
+            "project/filters/fix-orientation/image" => (
+                None,
+                filters
+                    .fix_orientation
+                    .image
+                    .as_ref()
+                    .and_then(|images| images.get(index))
+                    .map(|image| image.id),
+            ),
+            "project/filters/page-split/image" => (
+                None,
+                filters
+                    .page_split
+                    .image
+                    .as_ref()
+                    .and_then(|images| images.get(index))
+                    .map(|image| image.id),
+            ),
+            "project/filters/deskew/page" => (
+                filters
+                    .deskew
+                    .page
+                    .as_ref()
+                    .and_then(|pages| pages.get(index))
+                    .map(|page| page.id),
+                None,
+            ),
+            "project/filters/select-content/page" => (
+                filters
+                    .select_content
+                    .page
+                    .as_ref()
+                    .and_then(|pages| pages.get(index))
+                    .map(|page| page.id),
+                None,
+            ),
+            "project/filters/page-layout/page" => (
+                filters
+                    .page_layout
+                    .page
+                    .as_ref()
+                    .and_then(|pages| pages.get(index))
+                    .map(|page| page.id),
+                None,
+            ),
+            "project/filters/output/page" => {
+                (filters.output.page.get(index).map(|page| page.id), None)
+            }
+            _ => (None, None),
+        };
+        match ids {
+            (Some(id), _) => {
+                page = Some(id);
+                path.push_str(&format!("[id={}]", id));
+            }
+            (_, Some(id)) => {
+                image = Some(id);
+                path.push_str(&format!("[id={}]", id));
+            }
+            (None, None) => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    (path, page, image)
+}
+
+fn message(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+
+    let param = |name: &str| error.params.get(name).filter(|value| !value.is_null());
+    match error.code.as_ref() {
+        "range" => {
+            let value = param("value").map_or("value".to_owned(), Value::to_string);
+            match (param("min"), param("max")) {
+                (Some(min), Some(max)) => format!("{} is not between {} and {}", value, min, max),
+                (Some(min), None) => format!("{} is less than {}", value, min),
+                (None, Some(max)) => format!("{} is greater than {}", value, max),
+                (None, None) => format!("{} is out of range", value),
+            }
+        }
+        "length" => {
+            let count = param("value").and_then(Value::as_array).map_or(0, Vec::len);
+            match (param("min"), param("max")) {
+                (Some(min), Some(max)) => {
+                    format!("{} items where {} to {} are expected", count, min, max)
+                }
+                (Some(min), None) => format!("{} items where at least {} are expected", count, min),
+                (None, Some(max)) => format!("{} items where at most {} are expected", count, max),
+                (None, None) => format!("{} items", count),
+            }
+        }
+        code => code.replace('_', " "),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::de::from_str;
+
+    use crate::{
+        id::{ImageId, PageId},
+        project::test::ORIGINAL_CONTENT,
+        ProjectXML,
+    };
+
+    use super::{validate, Problem, Severity};
+
+    #[test]
+    fn it_reports_nothing_for_valid_projects() {
+        let project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        assert!(validate(&project).is_empty());
+    }
+
+    #[test]
+    fn it_flattens_validation_errors() {
+        let content = ORIGINAL_CONTENT
+            .replacen(
+                r#"<page id="7"><params angle="0.125""#,
+                r#"<page id="7"><params angle="50""#,
+                1,
+            )
+            .replacen(
+                r#"<image id="6" layoutType="single-cut">"#,
+                r#"<image id="6" layoutType="two-pages">"#,
+                1,
+            );
+        let project: ProjectXML = from_str(&content).unwrap();
+        let report = validate(&project);
+        assert_eq!(
+            report.problems,
+            vec![
+                Problem {
+                    code: "range".into(),
+                    severity: Severity::Error,
+                    page: Some(PageId(7)),
+                    image: None,
+                    path: "project/filters/deskew/page[id=7]/params/@angle".to_owned(),
+                    message: "50.0 is not between -45.0 and 45.0".to_owned(),
+                },
+                Problem {
+                    code: "layout_type_mismatch".into(),
+                    severity: Severity::Error,
+                    page: None,
+                    image: Some(ImageId(6)),
+                    path: "project/filters/page-split/image[id=6]".to_owned(),
+                    message: "layout types do not match".to_owned(),
+                },
+            ]
+        );
+        assert!(report.has_errors());
+
+        assert_eq!(
+            report.to_string(),
+            "error[range]: project/filters/deskew/page[id=7]/params/@angle: 50.0 is not between -45.0 and 45.0\n\
+             error[layout_type_mismatch]: project/filters/page-split/image[id=6]: layout types do not match\n"
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["problems"][1]["code"], "layout_type_mismatch");
+        assert_eq!(json["problems"][1]["image"], 6);
+        assert!(json["problems"][1].get("page").is_none());
+    }
+}
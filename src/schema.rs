@@ -0,0 +1,649 @@
+use std::{fmt::Write, rc::Rc};
+
+use schemars::{
+    gen::SchemaSettings,
+    schema::{InstanceType, RootSchema, Schema, SchemaObject, SingleOrVec},
+    Map,
+};
+
+use super::ProjectXML;
+
+// fields written as XML attributes are named with an @ prefix on both
+// the serde and the schema side, everything else is a child element
+
+/// JSON Schema of the serde rendering of [`ProjectXML`].
+pub fn json_schema() -> RootSchema {
+    SchemaSettings::draft07()
+        .with(|settings| settings.option_add_null_type = false)
+        .into_generator()
+        .into_root_schema_for::<ProjectXML>()
+}
+
+/// XML Schema of the project file, derived from [`json_schema`].
+pub fn xsd() -> String {
+    let root = json_schema();
+    let mut writer = XsdWriter {
+        layout: Layout {
+            definitions: &root.definitions,
+        },
+        out: String::new(),
+    };
+
+    writer.out.push_str(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<xs:schema xmlns:xs=\"http://www.w3.org/2001/XMLSchema\">\n",
+        "  <xs:element name=\"project\" type=\"ProjectXML\"/>\n",
+    ));
+
+    let members = writer.layout.members(&root.schema);
+    writer.complex_type(Some("ProjectXML"), &members, 1);
+
+    for (name, schema) in root.definitions.iter() {
+        let schema = object(schema);
+        if is_enumeration(schema) {
+            writer.simple_type(Some(name), &Simple::from_schema(schema), 1);
+        } else {
+            let members = writer.layout.members(schema);
+            writer.complex_type(Some(name), &members, 1);
+        }
+    }
+
+    writer.out.push_str("</xs:schema>\n");
+    writer.out
+}
+
+/// The attributes and child elements the project model declares on an element,
+/// as read from its JSON Schema.
+pub(crate) struct Declarations {
+    schema: Rc<RootSchema>,
+    members: Vec<Member>,
+}
+
+impl Declarations {
+    /// Those of the root `project` element.
+    pub(crate) fn project() -> Self {
+        let schema = Rc::new(json_schema());
+        let members = Layout {
+            definitions: &schema.definitions,
+        }
+        .members(&schema.schema);
+        Declarations { schema, members }
+    }
+
+    pub(crate) fn has_attribute(&self, name: &str) -> bool {
+        self.members
+            .iter()
+            .any(|member| member.attribute && member.name == name)
+    }
+
+    /// Those of a child element, if the model declares it.
+    pub(crate) fn child(&self, name: &str) -> Option<Declarations> {
+        let member = self
+            .members
+            .iter()
+            .find(|member| !member.attribute && member.name == name)?;
+        let layout = Layout {
+            definitions: &self.schema.definitions,
+        };
+        let members = layout.complex(&member.r#type).unwrap_or_default();
+        Some(Declarations {
+            schema: self.schema.clone(),
+            members,
+        })
+    }
+}
+
+/// The attributes and elements the project model declares, as read from its JSON Schema.
+struct Layout<'a> {
+    definitions: &'a Map<String, Schema>,
+}
+
+struct XsdWriter<'a> {
+    layout: Layout<'a>,
+    out: String,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Member {
+    name: String,
+    attribute: bool,
+    r#type: Type,
+    min_occurs: u32,
+    max_occurs: Option<u32>, // None means unbounded
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Type {
+    Named(String),
+    Simple(Simple),
+    Complex(Vec<Member>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+struct Simple {
+    base: &'static str,
+    enumeration: Vec<String>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+}
+
+impl Simple {
+    fn from_schema(schema: &SchemaObject) -> Self {
+        // enums with documented variants come out as a oneOf of single values
+        if let Some(variants) = one_of_values(schema) {
+            let mut variants = variants.map(Simple::from_schema);
+            let mut simple = variants.next().unwrap();
+            for variant in variants {
+                simple.enumeration.extend(variant.enumeration);
+            }
+            return simple;
+        }
+
+        let base = match (&schema.instance_type, schema.format.as_deref()) {
+            (Some(SingleOrVec::Single(t)), format) => match (**t, format) {
+                (InstanceType::Integer, Some("uint8")) => "xs:unsignedByte",
+                (InstanceType::Integer, Some("uint16")) => "xs:unsignedShort",
+                (InstanceType::Integer, Some("uint32")) => "xs:unsignedInt",
+                (InstanceType::Integer, Some("uint64")) => "xs:unsignedLong",
+                (InstanceType::Integer, Some("int8")) => "xs:byte",
+                (InstanceType::Integer, Some("int16")) => "xs:short",
+                (InstanceType::Integer, Some("int32")) => "xs:int",
+                (InstanceType::Integer, Some("int64")) => "xs:long",
+                (InstanceType::Integer, _) => "xs:integer",
+                (InstanceType::Number, _) => "xs:double",
+                (InstanceType::Boolean, _) => "xs:boolean",
+                _ => "xs:string",
+            },
+            _ => "xs:string",
+        };
+
+        let enumeration = schema
+            .enum_values
+            .iter()
+            .flatten()
+            .chain(schema.const_value.iter())
+            .filter_map(|value| value.as_str().map(str::to_owned))
+            .collect();
+
+        let (mut minimum, maximum) = match &schema.number {
+            Some(number) => (number.minimum, number.maximum),
+            None => (None, None),
+        };
+
+        // implied by the unsigned base type
+        if base.starts_with("xs:unsigned") && minimum == Some(0f64) {
+            minimum = None;
+        }
+
+        Simple {
+            base,
+            enumeration,
+            minimum,
+            maximum,
+        }
+    }
+
+    fn is_plain(&self) -> bool {
+        self.enumeration.is_empty() && self.minimum.is_none() && self.maximum.is_none()
+    }
+
+    fn bound(&self, value: f64) -> String {
+        if self.base == "xs:double" {
+            value.to_string()
+        } else {
+            (value as i64).to_string()
+        }
+    }
+}
+
+fn object(schema: &Schema) -> &SchemaObject {
+    match schema {
+        Schema::Object(schema) => schema,
+        Schema::Bool(_) => panic!("boolean schemas are not generated for the project model"),
+    }
+}
+
+fn is_enumeration(schema: &SchemaObject) -> bool {
+    schema.enum_values.is_some() || schema.const_value.is_some() || one_of_values(schema).is_some()
+}
+
+/// The variants of a `oneOf` made of nothing but enumerations.
+fn one_of_values(schema: &SchemaObject) -> Option<impl Iterator<Item = &SchemaObject>> {
+    let variants = schema.subschemas.as_ref()?.one_of.as_ref()?;
+    let all_values = !variants.is_empty()
+        && variants
+            .iter()
+            .all(|variant| matches!(variant, Schema::Object(variant) if is_enumeration(variant)));
+    all_values.then(|| variants.iter().map(object))
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl<'a> Layout<'a> {
+    fn resolve(&self, schema: &'a SchemaObject) -> (Option<&'a str>, &'a SchemaObject) {
+        match &schema.reference {
+            Some(reference) => {
+                let name = reference.trim_start_matches("#/definitions/");
+                (Some(name), object(&self.definitions[name]))
+            }
+            None => (None, schema),
+        }
+    }
+
+    /// Flattens an object schema, or a union of object schemas, into its attributes and elements.
+    fn members(&self, schema: &'a SchemaObject) -> Vec<Member> {
+        let (_, schema) = self.resolve(schema);
+
+        if let Some(subschemas) = &schema.subschemas {
+            let variants = subschemas
+                .one_of
+                .iter()
+                .chain(subschemas.any_of.iter())
+                .chain(subschemas.all_of.iter())
+                .flatten()
+                .map(|variant| self.members(object(variant)))
+                .collect::<Vec<_>>();
+            return self.merge(variants);
+        }
+
+        let Some(object_validation) = &schema.object else {
+            return vec![];
+        };
+
+        object_validation
+            .properties
+            .iter()
+            .map(|(name, property)| {
+                let required = object_validation.required.contains(name);
+                self.member(name, object(property), required)
+            })
+            .collect()
+    }
+
+    fn member(&self, name: &str, schema: &'a SchemaObject, required: bool) -> Member {
+        let (attribute, name) = match name.strip_prefix('@') {
+            Some(name) => (true, name),
+            None => (false, name),
+        };
+
+        let (min_occurs, max_occurs, item) = match &schema.array {
+            Some(array) => {
+                let item = match &array.items {
+                    Some(SingleOrVec::Single(item)) => object(item),
+                    _ => panic!("tuple arrays are not generated for the project model"),
+                };
+                let min = if required {
+                    array.min_items.unwrap_or(1)
+                } else {
+                    0
+                };
+                (min, array.max_items, item)
+            }
+            None => (u32::from(required), Some(1), schema),
+        };
+
+        Member {
+            name: name.to_owned(),
+            attribute,
+            r#type: self.r#type(item),
+            min_occurs,
+            max_occurs,
+        }
+    }
+
+    fn r#type(&self, schema: &'a SchemaObject) -> Type {
+        match self.resolve(schema) {
+            (Some(name), _) => Type::Named(name.to_owned()),
+            (None, schema) if is_enumeration(schema) => Type::Simple(Simple::from_schema(schema)),
+            (None, schema) if schema.object.is_some() || schema.subschemas.is_some() => {
+                Type::Complex(self.members(schema))
+            }
+            (None, schema) => Type::Simple(Simple::from_schema(schema)),
+        }
+    }
+
+    /// Merges the members of the variants of an untagged or internally tagged enum.
+    /// A member is only required if every variant requires it.
+    fn merge(&self, variants: Vec<Vec<Member>>) -> Vec<Member> {
+        let count = variants.len();
+        let mut merged: Vec<(Member, usize)> = vec![];
+
+        for variant in variants {
+            // members missing from what was merged so far are inserted after the
+            // previous member of the same variant, so that the element order holds
+            let mut cursor = 0;
+            for member in variant {
+                match merged.iter().position(|(existing, _)| {
+                    existing.name == member.name && existing.attribute == member.attribute
+                }) {
+                    Some(index) => {
+                        let (existing, occurrences) = &mut merged[index];
+                        *occurrences += 1;
+                        existing.min_occurs = existing.min_occurs.min(member.min_occurs);
+                        existing.max_occurs = match (existing.max_occurs, member.max_occurs) {
+                            (Some(a), Some(b)) => Some(a.max(b)),
+                            _ => None,
+                        };
+                        existing.r#type = self.merge_types(&existing.r#type, &member.r#type);
+                        cursor = index + 1;
+                    }
+                    None => {
+                        merged.insert(cursor, (member, 1));
+                        cursor += 1;
+                    }
+                }
+            }
+        }
+
+        merged
+            .into_iter()
+            .map(|(mut member, occurrences)| {
+                if occurrences < count {
+                    member.min_occurs = 0;
+                }
+                member
+            })
+            .collect()
+    }
+
+    fn merge_types(&self, a: &Type, b: &Type) -> Type {
+        match (a, b) {
+            (a, b) if a == b => a.clone(),
+            (Type::Simple(a), Type::Simple(b)) if a.base == b.base => {
+                let mut enumeration = a.enumeration.clone();
+                for value in b.enumeration.iter() {
+                    if !enumeration.contains(value) {
+                        enumeration.push(value.clone());
+                    }
+                }
+                Type::Simple(Simple {
+                    base: a.base,
+                    enumeration,
+                    minimum: a.minimum.zip(b.minimum).map(|(a, b)| a.min(b)),
+                    maximum: a.maximum.zip(b.maximum).map(|(a, b)| a.max(b)),
+                })
+            }
+            (a, b) => match (self.complex(a), self.complex(b)) {
+                (Some(a), Some(b)) => Type::Complex(self.merge(vec![a, b])),
+                _ => panic!("cannot merge schema types {:?} and {:?}", a, b),
+            },
+        }
+    }
+
+    /// The members of a complex type, whether named or not.
+    fn complex(&self, r#type: &Type) -> Option<Vec<Member>> {
+        match r#type {
+            Type::Named(name) => Some(self.members(object(&self.definitions[name]))),
+            Type::Complex(members) => Some(members.clone()),
+            Type::Simple(_) => None,
+        }
+    }
+}
+
+impl<'a> XsdWriter<'a> {
+    fn indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            self.out.push_str("  ");
+        }
+    }
+
+    fn simple_type(&mut self, name: Option<&str>, simple: &Simple, depth: usize) {
+        self.indent(depth);
+        match name {
+            Some(name) => writeln!(self.out, "<xs:simpleType name=\"{}\">", name).unwrap(),
+            None => self.out.push_str("<xs:simpleType>\n"),
+        }
+
+        self.indent(depth + 1);
+        writeln!(self.out, "<xs:restriction base=\"{}\">", simple.base).unwrap();
+        for value in simple.enumeration.iter() {
+            self.indent(depth + 2);
+            writeln!(self.out, "<xs:enumeration value=\"{}\"/>", escape(value)).unwrap();
+        }
+        if let Some(minimum) = simple.minimum {
+            self.indent(depth + 2);
+            writeln!(
+                self.out,
+                "<xs:minInclusive value=\"{}\"/>",
+                simple.bound(minimum)
+            )
+            .unwrap();
+        }
+        if let Some(maximum) = simple.maximum {
+            self.indent(depth + 2);
+            writeln!(
+                self.out,
+                "<xs:maxInclusive value=\"{}\"/>",
+                simple.bound(maximum)
+            )
+            .unwrap();
+        }
+        self.indent(depth + 1);
+        self.out.push_str("</xs:restriction>\n");
+
+        self.indent(depth);
+        self.out.push_str("</xs:simpleType>\n");
+    }
+
+    fn complex_type(&mut self, name: Option<&str>, members: &[Member], depth: usize) {
+        self.indent(depth);
+        match name {
+            Some(name) => write!(self.out, "<xs:complexType name=\"{}\"", name).unwrap(),
+            None => self.out.push_str("<xs:complexType"),
+        }
+
+        let (attributes, elements): (Vec<_>, Vec<_>) =
+            members.iter().partition(|member| member.attribute);
+
+        if attributes.is_empty() && elements.is_empty() {
+            self.out.push_str("/>\n");
+            return;
+        }
+        self.out.push_str(">\n");
+
+        if !elements.is_empty() {
+            self.indent(depth + 1);
+            self.out.push_str("<xs:sequence>\n");
+            for element in elements {
+                self.element(element, depth + 2);
+            }
+            self.indent(depth + 1);
+            self.out.push_str("</xs:sequence>\n");
+        }
+
+        for attribute in attributes {
+            self.attribute(attribute, depth + 1);
+        }
+
+        self.indent(depth);
+        self.out.push_str("</xs:complexType>\n");
+    }
+
+    fn occurs(&mut self, member: &Member) {
+        if member.min_occurs != 1 {
+            write!(self.out, " minOccurs=\"{}\"", member.min_occurs).unwrap();
+        }
+        match member.max_occurs {
+            Some(1) => {}
+            Some(max) => write!(self.out, " maxOccurs=\"{}\"", max).unwrap(),
+            None => self.out.push_str(" maxOccurs=\"unbounded\""),
+        }
+    }
+
+    fn element(&mut self, member: &Member, depth: usize) {
+        self.indent(depth);
+        write!(self.out, "<xs:element name=\"{}\"", member.name).unwrap();
+        self.occurs(member);
+
+        match &member.r#type {
+            Type::Named(name) => writeln!(self.out, " type=\"{}\"/>", name).unwrap(),
+            Type::Simple(simple) if simple.is_plain() => {
+                writeln!(self.out, " type=\"{}\"/>", simple.base).unwrap()
+            }
+            Type::Simple(simple) => {
+                self.out.push_str(">\n");
+                self.simple_type(None, simple, depth + 1);
+                self.indent(depth);
+                self.out.push_str("</xs:element>\n");
+            }
+            Type::Complex(members) => {
+                self.out.push_str(">\n");
+                self.complex_type(None, members, depth + 1);
+                self.indent(depth);
+                self.out.push_str("</xs:element>\n");
+            }
+        }
+    }
+
+    fn attribute(&mut self, member: &Member, depth: usize) {
+        self.indent(depth);
+        write!(self.out, "<xs:attribute name=\"{}\"", member.name).unwrap();
+        if member.min_occurs > 0 {
+            self.out.push_str(" use=\"required\"");
+        }
+
+        match &member.r#type {
+            Type::Named(name) => writeln!(self.out, " type=\"{}\"/>", name).unwrap(),
+            Type::Simple(simple) if simple.is_plain() => {
+                writeln!(self.out, " type=\"{}\"/>", simple.base).unwrap()
+            }
+            Type::Simple(simple) => {
+                self.out.push_str(">\n");
+                self.simple_type(None, simple, depth + 1);
+                self.indent(depth);
+                self.out.push_str("</xs:attribute>\n");
+            }
+            Type::Complex(_) => panic!("attribute {} cannot hold a complex type", member.name),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{env, fs, io, process::Command};
+
+    use quick_xml::{events::Event, Reader};
+
+    use crate::project::test::{ORIGINAL_CONTENT, ZONED_CONTENT};
+
+    use super::{json_schema, xsd};
+
+    fn attribute_declaration<'a>(xsd: &'a str, name: &str) -> &'a str {
+        let start = xsd
+            .find(&format!("<xs:attribute name=\"{}\"", name))
+            .unwrap();
+        let end = start + xsd[start..].find("</xs:attribute>").unwrap();
+        &xsd[start..end]
+    }
+
+    #[test]
+    fn it_carries_validation_ranges_into_json_schema() {
+        let schema = serde_json::to_value(json_schema()).unwrap();
+
+        let bw = &schema["definitions"]["BW"]["properties"];
+        assert_eq!(bw["@sauvolaCoef"]["minimum"], 0.01);
+        assert_eq!(bw["@sauvolaCoef"]["maximum"], 9.99);
+        assert_eq!(bw["@windowSize"]["minimum"], 3.0);
+        assert_eq!(bw["@windowSize"]["maximum"], 9999.0);
+
+        let dewarping_options = &schema["definitions"]["DewarpingOptions"]["properties"];
+        assert_eq!(dewarping_options["@postDeskewAngle"]["minimum"], -45.0);
+        assert_eq!(dewarping_options["@postDeskewAngle"]["maximum"], 45.0);
+
+        let rect = &schema["definitions"]["Rect"]["properties"];
+        assert_eq!(rect["@width"]["minimum"], 0.0);
+        assert_eq!(rect["@height"]["minimum"], 0.0);
+        let region = &schema["definitions"]["NonNegativeRegionRect"]["properties"];
+        assert_eq!(region["@x"]["minimum"], 0.0);
+        assert_eq!(region["@y"]["minimum"], 0.0);
+    }
+
+    #[test]
+    fn it_carries_validation_ranges_into_xsd() {
+        let xsd = xsd();
+
+        let sauvola_coeff = attribute_declaration(&xsd, "sauvolaCoef");
+        assert!(sauvola_coeff.contains("<xs:restriction base=\"xs:double\">"));
+        assert!(sauvola_coeff.contains("<xs:minInclusive value=\"0.01\"/>"));
+        assert!(sauvola_coeff.contains("<xs:maxInclusive value=\"9.99\"/>"));
+
+        let window_size = attribute_declaration(&xsd, "windowSize");
+        assert!(window_size.contains("<xs:restriction base=\"xs:unsignedShort\">"));
+        assert!(window_size.contains("<xs:minInclusive value=\"3\"/>"));
+        assert!(window_size.contains("<xs:maxInclusive value=\"9999\"/>"));
+
+        let start = xsd
+            .find("<xs:complexType name=\"NonNegativeRegionRect\">")
+            .unwrap();
+        let end = start + xsd[start..].find("</xs:complexType>").unwrap();
+        for name in ["height", "width", "x", "y"] {
+            let coordinate = attribute_declaration(&xsd[start..end], name);
+            assert!(coordinate.contains("<xs:minInclusive value=\"0\"/>"));
+        }
+    }
+
+    #[test]
+    fn it_declares_every_element_and_attribute_of_a_project() {
+        let xsd = xsd();
+        let mut reader = Reader::from_str(ORIGINAL_CONTENT);
+
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(element) | Event::Empty(element) => {
+                    let name = String::from_utf8_lossy(element.name().as_ref()).into_owned();
+                    assert!(xsd.contains(&format!("<xs:element name=\"{}\"", name)));
+
+                    for attribute in element.attributes() {
+                        let attribute = attribute.unwrap();
+                        let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+                        assert!(xsd.contains(&format!("<xs:attribute name=\"{}\"", key)));
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn it_validates_projects_against_xsd() {
+        // compiled and checked by libxml2, as the XSD is only ever consumed by other tools
+        let dir = env::temp_dir().join(format!("scantailor-project-xsd-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let schema = dir.join("project.xsd");
+        let original = dir.join("original.ScanTailor");
+        let zoned = dir.join("zoned.ScanTailor");
+        fs::write(&schema, xsd()).unwrap();
+        fs::write(&original, ORIGINAL_CONTENT).unwrap();
+        fs::write(&zoned, ZONED_CONTENT).unwrap();
+
+        let output = Command::new("xmllint")
+            .arg("--noout")
+            .arg("--schema")
+            .arg(&schema)
+            .arg(&original)
+            .arg(&zoned)
+            .output();
+        fs::remove_dir_all(&dir).unwrap();
+
+        // skipped where libxml2 is not installed, as nothing in the crate needs it
+        let output = match output {
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                eprintln!("xmllint not found, skipping the XSD check");
+                return;
+            }
+            output => output.unwrap(),
+        };
+
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
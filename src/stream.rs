@@ -0,0 +1,384 @@
+use std::{
+    fmt::Display,
+    io::{self, BufRead, Read},
+};
+
+use anyhow::{anyhow, Result};
+use quick_xml::{
+    events::{BytesStart, Event},
+    Writer,
+};
+use serde::de::DeserializeOwned;
+
+use super::{
+    directories::Directory,
+    error::{self, ParseError},
+    files::File,
+    filters::{
+        common::image_settings::ImageSettingsPage, deskew, fix_orientation, output, page_layout,
+        page_split, select_content,
+    },
+    images::Image,
+    pages::Page,
+};
+
+/// An entry of a project file, deserialized on its own.
+#[derive(Debug)]
+pub enum Entry {
+    Directory(Directory),
+    File(File),
+    Image(Image),
+    Page(Page),
+    FixOrientation(fix_orientation::Image),
+    /// image params of a page, as set in the fix orientation filter
+    FixOrientationImageSettings(ImageSettingsPage),
+    PageSplit(page_split::Image),
+    Deskew(deskew::Page),
+    /// image params of a page, as set in the deskew filter
+    DeskewImageSettings(ImageSettingsPage),
+    SelectContent(select_content::Page),
+    PageLayout(page_layout::Page),
+    Output(Box<output::Page>),
+}
+
+type Parse = fn(&str) -> Result<Entry, ParseError>;
+
+fn parse<T: DeserializeOwned>(content: &str, entry: fn(T) -> Entry) -> Result<Entry, ParseError> {
+    error::from_str(content).map(entry)
+}
+
+// where each kind of entry is found, by the path of its parent
+const ENTRIES: &[(&str, &str, Parse)] = &[
+    ("project/directories", "directory", |content| {
+        parse(content, Entry::Directory)
+    }),
+    ("project/files", "file", |content| {
+        parse(content, Entry::File)
+    }),
+    ("project/images", "image", |content| {
+        parse(content, Entry::Image)
+    }),
+    ("project/pages", "page", |content| {
+        parse(content, Entry::Page)
+    }),
+    ("project/filters/fix-orientation", "image", |content| {
+        parse(content, Entry::FixOrientation)
+    }),
+    (
+        "project/filters/fix-orientation/image-settings",
+        "page",
+        |content| parse(content, Entry::FixOrientationImageSettings),
+    ),
+    ("project/filters/page-split", "image", |content| {
+        parse(content, Entry::PageSplit)
+    }),
+    ("project/filters/deskew", "page", |content| {
+        parse(content, Entry::Deskew)
+    }),
+    ("project/filters/deskew/image-settings", "page", |content| {
+        parse(content, Entry::DeskewImageSettings)
+    }),
+    ("project/filters/select-content", "page", |content| {
+        parse(content, Entry::SelectContent)
+    }),
+    ("project/filters/page-layout", "page", |content| {
+        parse(content, Entry::PageLayout)
+    }),
+    ("project/filters/output", "page", |content| {
+        parse(content, |page| Entry::Output(Box::new(page)))
+    }),
+];
+
+/// Reads the entries of a project file one at a time, holding no more
+/// than the entry being read, for scanning projects too large to load.
+pub struct Reader<R: BufRead> {
+    reader: quick_xml::Reader<Lines<R>>,
+    path: Vec<String>,
+    buf: Vec<u8>,
+    /// set once the end of the file or an error past which nothing can be read is reached
+    done: bool,
+}
+
+/// A source counting the lines read from it, to locate errors without holding the file.
+struct Lines<R: BufRead> {
+    source: R,
+    /// 1-based
+    line: usize,
+    /// 1-based, in characters
+    column: usize,
+}
+
+impl<R: BufRead> Read for Lines<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.fill_buf()?.len().min(buf.len());
+        buf[..read].copy_from_slice(&self.fill_buf()?[..read]);
+        self.consume(read);
+        Ok(read)
+    }
+}
+
+impl<R: BufRead> BufRead for Lines<R> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.source.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        if let Ok(buf) = self.source.fill_buf() {
+            for byte in &buf[..amount.min(buf.len())] {
+                if *byte == b'\n' {
+                    self.line += 1;
+                    self.column = 1;
+                } else if *byte & 0xC0 != 0x80 {
+                    // counting every byte that does not continue a UTF-8 sequence
+                    self.column += 1;
+                }
+            }
+        }
+        self.source.consume(amount);
+    }
+}
+
+impl<'a> From<&'a str> for Reader<&'a [u8]> {
+    fn from(content: &'a str) -> Self {
+        Reader::new(content.as_bytes())
+    }
+}
+
+impl<R: BufRead> Reader<R> {
+    pub fn new(source: R) -> Self {
+        // whitespace is kept, so that entries are copied out as they are in the file
+        let reader = quick_xml::Reader::from_reader(Lines {
+            source,
+            line: 1,
+            column: 1,
+        });
+        Reader {
+            reader,
+            path: vec![],
+            buf: vec![],
+            done: false,
+        }
+    }
+
+    fn location(&self) -> Position {
+        let lines = self.reader.get_ref();
+        Position {
+            offset: self.reader.buffer_position(),
+            line: lines.line,
+            column: lines.column,
+        }
+    }
+
+    fn next_entry(&mut self) -> Result<Option<Entry>, ParseError> {
+        if self.done {
+            return Ok(None);
+        }
+        loop {
+            self.buf.clear();
+            let position = self.location();
+            let (start, empty) = match self.reader.read_event_into(&mut self.buf) {
+                Ok(Event::Start(start)) => (start.into_owned(), false),
+                Ok(Event::Empty(start)) => (start.into_owned(), true),
+                Ok(Event::End(_)) => {
+                    self.path.pop();
+                    continue;
+                }
+                Ok(Event::Eof) if self.path.is_empty() => {
+                    self.done = true;
+                    return Ok(None);
+                }
+                Ok(Event::Eof) => {
+                    self.done = true;
+                    return Err(position.error(&self.path.join("/"), "unexpected end of project"));
+                }
+                Ok(_) => continue,
+                Err(error) => {
+                    self.done = true;
+                    return Err(position.error(&self.path.join("/"), error));
+                }
+            };
+
+            let name = String::from_utf8_lossy(start.name().as_ref()).into_owned();
+            let parent = self.path.join("/");
+            let Some((_, _, parse)) = ENTRIES
+                .iter()
+                .find(|(path, entry, _)| *path == parent && *entry == name)
+            else {
+                if !empty {
+                    self.path.push(name);
+                }
+                continue;
+            };
+
+            let content = self.copy_entry(start, empty).map_err(|error| {
+                self.done = true;
+                position.error(&format!("{}/{}", parent, name), error)
+            })?;
+            // an entry that does not deserialize is skipped, the next one can still be read
+            return parse(&content)
+                .map(Some)
+                .map_err(|error| position.locate(&parent, error));
+        }
+    }
+
+    /// Copies the entry out so that serde only ever sees that much of the file.
+    fn copy_entry(&mut self, start: BytesStart<'static>, empty: bool) -> Result<String> {
+        let mut writer = Writer::new(vec![]);
+        if empty {
+            writer.write_event(Event::Empty(start))?;
+        } else {
+            writer.write_event(Event::Start(start))?;
+            let mut depth = 0;
+            let mut buf = vec![];
+            loop {
+                let event = self.reader.read_event_into(&mut buf)?;
+                match &event {
+                    Event::Start(_) => depth += 1,
+                    Event::End(_) if depth == 0 => {
+                        writer.write_event(event)?;
+                        break;
+                    }
+                    Event::End(_) => depth -= 1,
+                    Event::Eof => return Err(anyhow!("unexpected end of entry")),
+                    _ => {}
+                }
+                writer.write_event(event)?;
+                buf.clear();
+            }
+        }
+        Ok(String::from_utf8(writer.into_inner())?)
+    }
+}
+
+/// Where an entry starts in the file.
+#[derive(Clone, Copy)]
+struct Position {
+    offset: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Position {
+    /// An error reading the entry at `path`, located at its start.
+    fn error(&self, path: &str, message: impl Display) -> ParseError {
+        ParseError {
+            message: message.to_string(),
+            offset: self.offset,
+            line: self.line,
+            column: self.column,
+            path: path.to_owned(),
+        }
+    }
+
+    /// Locates an error found in the entry once copied out of the file, below `parent`.
+    fn locate(&self, parent: &str, error: ParseError) -> ParseError {
+        ParseError {
+            message: error.message,
+            offset: self.offset + error.offset,
+            line: self.line + error.line - 1,
+            column: match error.line {
+                1 => self.column + error.column - 1,
+                _ => error.column,
+            },
+            path: match error.path.as_str() {
+                "" => parent.to_owned(),
+                path => format!("{}/{}", parent, path),
+            },
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().map_err(Into::into).transpose()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::BufReader;
+
+    use crate::{
+        error::ParseError,
+        id::{DirectoryId, ImageId, PageId},
+        project::test::ORIGINAL_CONTENT,
+    };
+
+    use super::{Entry, Reader};
+
+    #[test]
+    fn it_reads_every_entry() {
+        let entries = Reader::new(BufReader::new(ORIGINAL_CONTENT.as_bytes()))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(entries.len(), 23);
+
+        assert!(
+            matches!(&entries[0], Entry::Directory(directory) if directory.id == DirectoryId(1))
+        );
+        assert!(matches!(&entries[1], Entry::File(file) if file.name == "202311050607_0001.jpg"));
+        assert!(matches!(&entries[4], Entry::Image(image) if image.id == ImageId(6)));
+        assert!(matches!(&entries[5], Entry::Page(page) if page.id == PageId(4)));
+        assert!(matches!(&entries[8], Entry::FixOrientation(image) if image.id == ImageId(6)));
+        assert!(
+            matches!(&entries[9], Entry::FixOrientationImageSettings(page) if page.id == PageId(4))
+        );
+        assert!(
+            matches!(&entries[10], Entry::FixOrientationImageSettings(page) if page.id == PageId(7))
+        );
+        assert!(matches!(&entries[11], Entry::PageSplit(image) if image.id == ImageId(3)));
+        assert!(matches!(&entries[14], Entry::Deskew(page) if page.params.angle == 0.125));
+        assert!(matches!(
+            &entries[15],
+            Entry::DeskewImageSettings(page) if page.image_params.bw_threshold == 190
+        ));
+        assert!(matches!(&entries[16], Entry::DeskewImageSettings(page) if page.id == PageId(7)));
+        assert!(matches!(&entries[17], Entry::SelectContent(_)));
+        assert!(matches!(&entries[19], Entry::PageLayout(_)));
+        assert!(matches!(&entries[22], Entry::Output(page) if page.id == PageId(7)));
+    }
+
+    #[test]
+    fn it_reports_broken_entries() {
+        let content =
+            ORIGINAL_CONTENT.replacen(r#"<page id="4" imageId="3""#, r#"<page id="4""#, 1);
+        let entries = Reader::from(content.as_str()).collect::<Vec<_>>();
+        assert!(entries[5].is_err());
+        assert!(matches!(&entries[6], Ok(Entry::Page(page)) if page.id == PageId(7)));
+    }
+
+    #[test]
+    fn it_locates_errors_in_the_file() {
+        let content = ORIGINAL_CONTENT.replace("><", ">\n  <").replacen(
+            r#"<page id="7">
+  <params angle="0.125""#,
+            r#"<page id="7">
+  <params angle="tilted""#,
+            1,
+        );
+        let error = Reader::from(content.as_str())
+            .find_map(Result::err)
+            .unwrap()
+            .downcast::<ParseError>()
+            .unwrap();
+        assert_eq!(error.path, "project/filters/deskew/page[id=7]/params");
+        assert!(content[error.offset..].starts_with(r#"<params angle="tilted""#));
+        assert_eq!(
+            content.lines().nth(error.line - 1).unwrap(),
+            r#"  <params angle="tilted" mode="auto">"#
+        );
+        assert_eq!(error.column, 3);
+    }
+
+    #[test]
+    fn it_stops_at_the_end_of_truncated_projects() {
+        for end in ["<deskew>", r#"<page id="7"><params angle"#] {
+            let content = &ORIGINAL_CONTENT[..ORIGINAL_CONTENT.find(end).unwrap() + end.len()];
+            let entries = Reader::from(content).collect::<Vec<_>>();
+            assert_eq!(entries.iter().filter(|entry| entry.is_err()).count(), 1);
+            assert!(entries.last().unwrap().is_err());
+        }
+    }
+}
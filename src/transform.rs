@@ -0,0 +1,292 @@
+use crate::{
+    common::Point,
+    filters::{
+        common::{FilledOutline, Rect},
+        output::output_params::PartialTransform,
+    },
+    units::Px,
+};
+
+/// A 2D affine transform, laid out as a `QTransform` is, so that a point is mapped to
+/// `(m11 * x + m21 * y + dx, m12 * x + m22 * y + dy)`.
+///
+/// Angles are in degrees and turn clockwise on images, where y grows downwards, as the
+/// rotations and deskew angles ScanTailor stores do.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Transform {
+    pub m11: f64,
+    pub m12: f64,
+    pub m21: f64,
+    pub m22: f64,
+    pub dx: f64,
+    pub dy: f64,
+}
+
+/// A transform taken apart into a scale and shear, followed by a rotation, followed by a
+/// translation.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Decomposition {
+    /// in degrees
+    pub rotation: f64,
+    pub scale_x: f64,
+    /// negative if the transform mirrors
+    pub scale_y: f64,
+    /// how much x moves along with y, before the rotation
+    pub shear: f64,
+    pub dx: f64,
+    pub dy: f64,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        m11: 1f64,
+        m12: 0f64,
+        m21: 0f64,
+        m22: 1f64,
+        dx: 0f64,
+        dy: 0f64,
+    };
+
+    pub fn translation(dx: f64, dy: f64) -> Self {
+        Transform {
+            dx,
+            dy,
+            ..Transform::IDENTITY
+        }
+    }
+
+    pub fn rotation(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Transform {
+            m11: cos,
+            m12: sin,
+            m21: -sin,
+            m22: cos,
+            ..Transform::IDENTITY
+        }
+    }
+
+    pub fn scaling(sx: f64, sy: f64) -> Self {
+        Transform {
+            m11: sx,
+            m22: sy,
+            ..Transform::IDENTITY
+        }
+    }
+
+    /// Moves x along with y by `shear`.
+    pub fn shearing(shear: f64) -> Self {
+        Transform {
+            m21: shear,
+            ..Transform::IDENTITY
+        }
+    }
+
+    /// This transform followed by `next`, which is `self * next` with `QTransform`.
+    pub fn then(&self, next: &Transform) -> Self {
+        Transform {
+            m11: self.m11 * next.m11 + self.m12 * next.m21,
+            m12: self.m11 * next.m12 + self.m12 * next.m22,
+            m21: self.m21 * next.m11 + self.m22 * next.m21,
+            m22: self.m21 * next.m12 + self.m22 * next.m22,
+            dx: self.dx * next.m11 + self.dy * next.m21 + next.dx,
+            dy: self.dx * next.m12 + self.dy * next.m22 + next.dy,
+        }
+    }
+
+    pub fn determinant(&self) -> f64 {
+        self.m11 * self.m22 - self.m12 * self.m21
+    }
+
+    /// The transform undoing this one, unless it flattens the plane.
+    pub fn inverse(&self) -> Option<Self> {
+        let determinant = self.determinant();
+        if determinant == 0f64 || !determinant.is_finite() {
+            return None;
+        }
+        let (m11, m12, m21, m22) = (
+            self.m22 / determinant,
+            -self.m12 / determinant,
+            -self.m21 / determinant,
+            self.m11 / determinant,
+        );
+        Some(Transform {
+            m11,
+            m12,
+            m21,
+            m22,
+            dx: -(self.dx * m11 + self.dy * m21),
+            dy: -(self.dx * m12 + self.dy * m22),
+        })
+    }
+
+    /// Takes the transform apart, unless it flattens the plane.
+    pub fn decompose(&self) -> Option<Decomposition> {
+        let scale_x = self.m11.hypot(self.m12);
+        if scale_x == 0f64 || self.determinant() == 0f64 {
+            return None;
+        }
+        Some(Decomposition {
+            rotation: self.m12.atan2(self.m11).to_degrees(),
+            scale_x,
+            scale_y: self.determinant() / scale_x,
+            shear: (self.m11 * self.m21 + self.m12 * self.m22) / (scale_x * scale_x),
+            dx: self.dx,
+            dy: self.dy,
+        })
+    }
+
+    pub fn map_point(&self, point: &Point) -> Point {
+        let (x, y) = (point.x.0, point.y.0);
+        Point::new(
+            Px(self.m11 * x + self.m21 * y + self.dx),
+            Px(self.m12 * x + self.m22 * y + self.dy),
+        )
+    }
+
+    /// The bounding rectangle of the mapped corners, as `QTransform::mapRect` does.
+    pub fn map_rect(&self, rect: &Rect) -> Rect {
+        let corners = rect.corners().map(|corner| self.map_point(&corner));
+        Rect::bounding(&corners).unwrap()
+    }
+
+    pub fn map_outline(&self, outline: &FilledOutline) -> FilledOutline {
+        FilledOutline {
+            point: outline
+                .point
+                .iter()
+                .map(|point| self.map_point(point))
+                .collect(),
+        }
+    }
+}
+
+impl From<Decomposition> for Transform {
+    fn from(decomposition: Decomposition) -> Self {
+        Transform::shearing(decomposition.shear)
+            .then(&Transform::scaling(
+                decomposition.scale_x,
+                decomposition.scale_y,
+            ))
+            .then(&Transform::rotation(decomposition.rotation))
+            .then(&Transform::translation(decomposition.dx, decomposition.dy))
+    }
+}
+
+impl From<&PartialTransform> for Transform {
+    fn from(partial: &PartialTransform) -> Self {
+        Transform {
+            m11: partial.m11,
+            m12: partial.m12,
+            m21: partial.m21,
+            m22: partial.m22,
+            ..Transform::IDENTITY
+        }
+    }
+}
+
+/// ScanTailor only stores the part of a transform that is not a translation.
+impl From<&Transform> for PartialTransform {
+    fn from(transform: &Transform) -> Self {
+        PartialTransform {
+            m11: transform.m11,
+            m12: transform.m12,
+            m21: transform.m21,
+            m22: transform.m22,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::de::from_str;
+
+    use crate::{
+        common::Point,
+        filters::{common::Rect, output::output_params::PartialTransform},
+        project::test::ORIGINAL_CONTENT,
+        units::Px,
+        ProjectXML,
+    };
+
+    use super::Transform;
+
+    fn assert_near(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    fn assert_near_transform(a: &Transform, b: &Transform) {
+        for (a, b) in [
+            (a.m11, b.m11),
+            (a.m12, b.m12),
+            (a.m21, b.m21),
+            (a.m22, b.m22),
+            (a.dx, b.dx),
+            (a.dy, b.dy),
+        ] {
+            assert_near(a, b);
+        }
+    }
+
+    #[test]
+    fn it_composes_and_inverts() {
+        let transform = Transform::rotation(90f64).then(&Transform::translation(10f64, 0f64));
+        let point = transform.map_point(&Point::new(Px(1f64), Px(2f64)));
+        assert_near(point.x.0, 8f64);
+        assert_near(point.y.0, 1f64);
+
+        let inverse = transform.inverse().unwrap();
+        let back = inverse.map_point(&point);
+        assert_near(back.x.0, 1f64);
+        assert_near(back.y.0, 2f64);
+        assert_near_transform(&transform.then(&inverse), &Transform::IDENTITY);
+        assert_eq!(Transform::scaling(0f64, 1f64).inverse(), None);
+
+        let rect = Rect::new(Px(0f64), Px(0f64), Px(4f64), Px(2f64));
+        let mapped = Transform::rotation(90f64).map_rect(&rect);
+        assert_near(mapped.x.0, -2f64);
+        assert_near(mapped.width.0, 2f64);
+        assert_near(mapped.height.0, 4f64);
+    }
+
+    #[test]
+    fn it_decomposes() {
+        let transform = Transform::shearing(0.25)
+            .then(&Transform::scaling(2f64, -3f64))
+            .then(&Transform::rotation(30f64))
+            .then(&Transform::translation(5f64, 7f64));
+        let decomposition = transform.decompose().unwrap();
+        assert_near(decomposition.rotation, 30f64);
+        assert_near(decomposition.scale_x, 2f64);
+        assert_near(decomposition.scale_y, -3f64);
+        assert_near(decomposition.shear, 0.25);
+        assert_near_transform(&Transform::from(decomposition), &transform);
+    }
+
+    #[test]
+    fn it_reads_partial_transforms() {
+        let project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        let output = project.filters.output.page[0]
+            .output_params
+            .as_ref()
+            .unwrap();
+        let partial = &output.image.partial_transform;
+
+        // the image was turned by 90° and deskewed by 0.125°
+        let transform = Transform::from(partial);
+        let decomposition = transform.decompose().unwrap();
+        assert_near(decomposition.rotation, 90.125);
+        assert_near(decomposition.scale_x, 1f64);
+        assert_near(decomposition.scale_y, 1f64);
+
+        let written = PartialTransform::from(&transform.then(&Transform::translation(1f64, 2f64)));
+        assert_eq!(written.m11, partial.m11);
+        assert_eq!(written.m22, partial.m22);
+    }
+}
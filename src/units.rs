@@ -0,0 +1,257 @@
+use std::{
+    fmt::{self, Display},
+    ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign},
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_with::{DeserializeAs, SerializeAs};
+use validator::ValidationError;
+
+use crate::number::{format_double, QtNumber};
+
+pub const MM_PER_INCH: f64 = 25.4;
+
+// ScanTailor stores pixels, millimetres and dots per inch alike as plain doubles; these
+// keep a length from being used in another unit than the one it was read in. Pixel
+// lengths only make sense along with the DPI of their image, which conversions take.
+
+/// A length in pixels of an image.
+#[derive(
+    Serialize, Deserialize, JsonSchema, Clone, Copy, Default, PartialEq, PartialOrd, Debug,
+)]
+#[serde(transparent)]
+pub struct Px(pub f64);
+
+/// A length in millimetres on paper.
+#[derive(
+    Serialize, Deserialize, JsonSchema, Clone, Copy, Default, PartialEq, PartialOrd, Debug,
+)]
+#[serde(transparent)]
+pub struct Mm(pub f64);
+
+/// A length in inches on paper.
+#[derive(
+    Serialize, Deserialize, JsonSchema, Clone, Copy, Default, PartialEq, PartialOrd, Debug,
+)]
+#[serde(transparent)]
+pub struct Inch(pub f64);
+
+/// A resolution in dots per inch, along one axis of an image.
+#[derive(
+    Serialize, Deserialize, JsonSchema, Clone, Copy, Default, PartialEq, PartialOrd, Debug,
+)]
+#[serde(transparent)]
+pub struct Dpi(pub f64);
+
+impl Px {
+    /// The length on paper of pixels scanned at `dpi`, which is the horizontal DPI of
+    /// the image for widths and the vertical one for heights.
+    pub fn to_inch(self, dpi: Dpi) -> Inch {
+        Inch(self.0 / dpi.0)
+    }
+
+    pub fn to_mm(self, dpi: Dpi) -> Mm {
+        self.to_inch(dpi).to_mm()
+    }
+}
+
+impl Mm {
+    pub fn to_inch(self) -> Inch {
+        Inch(self.0 / MM_PER_INCH)
+    }
+
+    /// The pixels the length covers when scanned at `dpi`.
+    pub fn to_px(self, dpi: Dpi) -> Px {
+        self.to_inch().to_px(dpi)
+    }
+}
+
+impl Inch {
+    pub fn to_mm(self) -> Mm {
+        Mm(self.0 * MM_PER_INCH)
+    }
+
+    /// The pixels the length covers when scanned at `dpi`.
+    pub fn to_px(self, dpi: Dpi) -> Px {
+        Px(self.0 * dpi.0)
+    }
+}
+
+// a double in a unit, read and written as ScanTailor does
+macro_rules! quantity {
+    ($unit:ident, $suffix:literal) => {
+        impl $unit {
+            pub fn min(self, other: $unit) -> $unit {
+                $unit(self.0.min(other.0))
+            }
+
+            pub fn max(self, other: $unit) -> $unit {
+                $unit(self.0.max(other.0))
+            }
+        }
+
+        impl From<$unit> for f64 {
+            fn from(value: $unit) -> Self {
+                value.0
+            }
+        }
+
+        impl Display for $unit {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{} {}", format_double(self.0), $suffix)
+            }
+        }
+
+        // the ratio of two quantities
+        impl Div for $unit {
+            type Output = f64;
+
+            fn div(self, other: $unit) -> f64 {
+                self.0 / other.0
+            }
+        }
+
+        impl SerializeAs<$unit> for QtNumber {
+            fn serialize_as<S: Serializer>(
+                value: &$unit,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                QtNumber::serialize_as(&value.0, serializer)
+            }
+        }
+
+        impl<'de> DeserializeAs<'de, $unit> for QtNumber {
+            fn deserialize_as<D: Deserializer<'de>>(deserializer: D) -> Result<$unit, D::Error> {
+                <QtNumber as DeserializeAs<'de, f64>>::deserialize_as(deserializer).map($unit)
+            }
+        }
+    };
+}
+
+// a length, which adds up and scales
+macro_rules! unit {
+    ($unit:ident, $suffix:literal) => {
+        quantity!($unit, $suffix);
+
+        impl $unit {
+            pub fn abs(self) -> $unit {
+                $unit(self.0.abs())
+            }
+        }
+
+        impl Add for $unit {
+            type Output = $unit;
+
+            fn add(self, other: $unit) -> $unit {
+                $unit(self.0 + other.0)
+            }
+        }
+
+        impl AddAssign for $unit {
+            fn add_assign(&mut self, other: $unit) {
+                self.0 += other.0;
+            }
+        }
+
+        impl Sub for $unit {
+            type Output = $unit;
+
+            fn sub(self, other: $unit) -> $unit {
+                $unit(self.0 - other.0)
+            }
+        }
+
+        impl SubAssign for $unit {
+            fn sub_assign(&mut self, other: $unit) {
+                self.0 -= other.0;
+            }
+        }
+
+        impl Neg for $unit {
+            type Output = $unit;
+
+            fn neg(self) -> $unit {
+                $unit(-self.0)
+            }
+        }
+
+        impl Mul<f64> for $unit {
+            type Output = $unit;
+
+            fn mul(self, factor: f64) -> $unit {
+                $unit(self.0 * factor)
+            }
+        }
+
+        impl Div<f64> for $unit {
+            type Output = $unit;
+
+            fn div(self, divisor: f64) -> $unit {
+                $unit(self.0 / divisor)
+            }
+        }
+    };
+}
+
+unit!(Px, "px");
+unit!(Mm, "mm");
+unit!(Inch, "in");
+quantity!(Dpi, "dpi");
+
+/// Validates that a length is not negative, reported as `range(min = 0)` would, which
+/// only takes primitive numbers.
+pub(crate) fn validate_non_negative<T: Copy + Into<f64>>(value: &T) -> Result<(), ValidationError> {
+    if (*value).into() < 0f64 {
+        let mut error = ValidationError::new("range");
+        error.add_param("min".into(), &0);
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use quick_xml::de::from_str;
+
+    use crate::{common::Size, project::test::ORIGINAL_CONTENT, ProjectXML};
+
+    use super::{Dpi, Inch, Mm, Px};
+
+    #[test]
+    fn it_converts_between_units() {
+        assert_eq!(Inch(1f64).to_mm(), Mm(25.4));
+        assert_eq!(Mm(25.4).to_px(Dpi(600f64)), Px(600f64));
+        assert_eq!(Px(300f64).to_inch(Dpi(600f64)), Inch(0.5));
+        assert_eq!(Px(300f64).to_mm(Dpi(600f64)), Mm(12.7));
+        assert!((Mm(210f64).to_px(Dpi(300f64)).0 - 2480.314960629921).abs() < 1e-9);
+        assert!((Mm(210f64).to_px(Dpi(300f64)).to_mm(Dpi(300f64)).0 - 210f64).abs() < 1e-9);
+
+        assert_eq!(Px(10f64) + Px(2.5) - Px(0.5), Px(12f64));
+        assert_eq!(Mm(10f64) * 1.5 / 3f64, Mm(5f64));
+        assert_eq!(Px(3f64) / Px(4f64), 0.75);
+        assert_eq!(Mm(12.7).to_string(), "12.7 mm");
+        assert_eq!((-Px(4960f64)).to_string(), "-4960 px");
+        assert_eq!(Dpi(600f64).to_string(), "600 dpi");
+        assert_eq!(Px(-3f64).abs().max(Px(2f64)), Px(3f64));
+    }
+
+    #[test]
+    fn it_converts_sizes_with_the_image_dpi() {
+        let project: ProjectXML = from_str(ORIGINAL_CONTENT).unwrap();
+        let image = &project.images.image[0];
+        let params = &project.filters.page_layout.page.as_ref().unwrap()[0].params;
+
+        let content = Size {
+            height: params.content_rect.height,
+            width: params.content_rect.width,
+        }
+        .to_mm(&image.dpi);
+        assert!((content.width - params.content_size.width).0.abs() < 0.01);
+        assert!((content.height - params.content_size.height).0.abs() < 0.01);
+
+        let size = image.size.to_mm(&image.dpi);
+        assert!((size.width.0 - 297.01).abs() < 0.01);
+        assert_eq!(size.to_px(&image.dpi).height, Px(4961f64));
+    }
+}
@@ -0,0 +1,11 @@
+use std::borrow::Cow;
+
+use validator::ValidationError;
+
+/// Builds an error for a custom validation, with a stable code and an explanation.
+pub(crate) fn invalid(code: &'static str, message: &'static str) -> ValidationError {
+    ValidationError {
+        message: Some(Cow::Borrowed(message)),
+        ..ValidationError::new(code)
+    }
+}
@@ -0,0 +1,463 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    ops::Range,
+};
+
+use anyhow::{anyhow, Result};
+use quick_xml::{
+    escape::escape,
+    events::{BytesStart, Event},
+    Reader,
+};
+
+use crate::error::ParseError;
+
+/// A minimal XML element tree, used where the project has to be
+/// looked at or rewritten before it is handed over to serde.
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub(crate) enum Node {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    pub fn new(name: &str) -> Self {
+        Element {
+            name: name.to_owned(),
+            attributes: vec![],
+            children: vec![],
+        }
+    }
+
+    pub fn with_attributes(name: &str, attributes: &[(&str, &str)]) -> Self {
+        let mut element = Element::new(name);
+        for (key, value) in attributes {
+            element.set_attribute(key, value);
+        }
+        element
+    }
+
+    /// Parses a document, locating any error as [`ParseError`] does.
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(Element::read(content, true)?.0)
+    }
+
+    /// Parses a document, keeping whitespace between elements unless `trim`,
+    /// and returning where the root element starts and ends.
+    fn read(content: &str, trim: bool) -> Result<(Self, Range<usize>)> {
+        let mut reader = Reader::from_str(content);
+        reader.trim_text(trim);
+
+        // the elements being read, with where their start tag starts
+        let mut stack: Vec<(Element, usize)> = vec![];
+        let mut root_start = 0;
+        loop {
+            let start = reader.buffer_position();
+            if stack.is_empty() {
+                root_start = start;
+            }
+            match Element::read_event(&mut reader, &mut stack, start) {
+                Ok(Some(root)) => return Ok((root, root_start..reader.buffer_position())),
+                Ok(None) => {}
+                Err(error) => {
+                    let path = stack
+                        .iter()
+                        .map(|(element, _)| element.segment())
+                        .collect::<Vec<_>>()
+                        .join("/");
+                    let offset = stack.last().map_or(start, |(_, start)| *start);
+                    return Err(ParseError::new(content, offset, path, error.to_string()).into());
+                }
+            }
+        }
+    }
+
+    /// Reads the next event into the elements being read, returning the root once read.
+    fn read_event(
+        reader: &mut Reader<&[u8]>,
+        stack: &mut Vec<(Element, usize)>,
+        start: usize,
+    ) -> Result<Option<Self>> {
+        let element = match reader.read_event()? {
+            Event::Start(tag) => {
+                stack.push((Element::from_start(&tag)?, start));
+                return Ok(None);
+            }
+            Event::Empty(tag) => Element::from_start(&tag)?,
+            Event::End(_) => stack.pop().ok_or_else(|| anyhow!("unbalanced end tag"))?.0,
+            Event::Text(text) => {
+                if let Some((parent, _)) = stack.last_mut() {
+                    parent
+                        .children
+                        .push(Node::Text(text.unescape()?.into_owned()));
+                }
+                return Ok(None);
+            }
+            Event::CData(data) => {
+                if let Some((parent, _)) = stack.last_mut() {
+                    let text = String::from_utf8(data.into_inner().into_owned())?;
+                    parent.children.push(Node::Text(text));
+                }
+                return Ok(None);
+            }
+            Event::Eof => return Err(anyhow!("no root element")),
+            Event::Decl(_) | Event::Comment(_) | Event::PI(_) | Event::DocType(_) => {
+                return Ok(None)
+            }
+        };
+        match stack.last_mut() {
+            Some((parent, _)) => {
+                parent.children.push(Node::Element(element));
+                Ok(None)
+            }
+            None => Ok(Some(element)),
+        }
+    }
+
+    fn from_start(start: &BytesStart) -> Result<Self> {
+        let mut element = Element::new(std::str::from_utf8(start.name().as_ref())?);
+        for attribute in start.attributes() {
+            let attribute = attribute?;
+            element.attributes.push((
+                std::str::from_utf8(attribute.key.as_ref())?.to_owned(),
+                attribute.unescape_value()?.into_owned(),
+            ));
+        }
+        Ok(element)
+    }
+
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_attribute(&mut self, key: &str, value: &str) {
+        match self.attributes.iter_mut().find(|(k, _)| k == key) {
+            Some((_, v)) => *v = value.to_owned(),
+            None => self.attributes.push((key.to_owned(), value.to_owned())),
+        }
+    }
+
+    pub fn remove_attribute(&mut self, key: &str) -> Option<String> {
+        let index = self.attributes.iter().position(|(k, _)| k == key)?;
+        Some(self.attributes.remove(index).1)
+    }
+
+    /// Keeps attributes sorted by name, as ScanTailor writes them.
+    pub fn sort_attributes(&mut self) {
+        self.attributes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    pub fn elements_mut(&mut self) -> impl Iterator<Item = &mut Element> {
+        self.children.iter_mut().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|element| element.name == name)
+    }
+
+    pub fn child_mut(&mut self, name: &str) -> Option<&mut Element> {
+        self.elements_mut().find(|element| element.name == name)
+    }
+
+    pub fn children_mut<'a>(&'a mut self, name: &'a str) -> impl Iterator<Item = &'a mut Element> {
+        self.elements_mut()
+            .filter(move |element| element.name == name)
+    }
+
+    pub fn remove_child(&mut self, name: &str) -> Option<Element> {
+        let index = self.children.iter().position(|node| match node {
+            Node::Element(element) => element.name == name,
+            Node::Text(_) => false,
+        })?;
+        match self.children.remove(index) {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => unreachable!(),
+        }
+    }
+
+    /// Inserts `child` after the last of the `preceding` siblings present,
+    /// or first if there is none of them.
+    pub fn insert_child_after(&mut self, preceding: &[&str], child: Element) {
+        let index = self
+            .children
+            .iter()
+            .rposition(|node| match node {
+                Node::Element(element) => preceding.contains(&element.name.as_str()),
+                Node::Text(_) => false,
+            })
+            .map_or(0, |index| index + 1);
+        self.children.insert(index, Node::Element(child));
+    }
+
+    /// The path of this element below `parent`, with its id if it has one,
+    /// as in `project/filters/output/page[id=4]`.
+    pub fn path(&self, parent: &str) -> String {
+        format!("{}/{}", parent, self.segment())
+    }
+
+    fn segment(&self) -> String {
+        match self.attribute("id") {
+            Some(id) => format!("{}[id={}]", self.name, id),
+            None => self.name.clone(),
+        }
+    }
+
+    /// The path segments of the child elements, in order. Siblings of the same name
+    /// without an id are told apart by their position among them, as in `mapping[1]`.
+    pub fn child_segments(&self) -> Vec<String> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for element in self.elements() {
+            *counts.entry(&element.name).or_default() += 1;
+        }
+
+        let mut ordinals: HashMap<&str, usize> = HashMap::new();
+        self.elements()
+            .map(|element| {
+                let ordinal = ordinals.entry(&element.name).or_default();
+                *ordinal += 1;
+                match element.attribute("id") {
+                    Some(_) => element.segment(),
+                    None if counts[element.name.as_str()] > 1 => {
+                        format!("{}[{}]", element.name, *ordinal - 1)
+                    }
+                    None => element.name.clone(),
+                }
+            })
+            .collect()
+    }
+
+    /// Finds the element at a path as made of [`Element::path`] and
+    /// [`Element::child_segments`], starting with the name of this element.
+    pub fn find_path_mut(&mut self, path: &str) -> Option<&mut Element> {
+        let mut segments = path.split('/');
+        if segments.next()? != self.segment() {
+            return None;
+        }
+
+        let mut current = self;
+        for segment in segments {
+            current = match segment.strip_suffix(']').and_then(|s| s.split_once('[')) {
+                Some((name, key)) => match key.strip_prefix("id=") {
+                    Some(id) => current.elements_mut().find(|element| {
+                        element.name == name && element.attribute("id") == Some(id)
+                    })?,
+                    None => current
+                        .elements_mut()
+                        .filter(|element| element.name == name)
+                        .nth(key.parse().ok()?)?,
+                },
+                None => current.child_mut(segment)?,
+            };
+        }
+        Some(current)
+    }
+
+    /// Finds the elements at `path`, a `/` separated list of element names below this one.
+    pub fn find_all_mut<'a>(&'a mut self, path: &str) -> Vec<&'a mut Element> {
+        let mut current = vec![self];
+        for name in path.split('/').filter(|name| !name.is_empty()) {
+            current = current
+                .into_iter()
+                .flat_map(|element| {
+                    element
+                        .elements_mut()
+                        .filter(|element| element.name == name)
+                })
+                .collect();
+        }
+        current
+    }
+}
+
+/// A document as it was written, with the whitespace between its elements and
+/// what comes before and after the root element, such as the XML declaration.
+#[derive(Clone, Debug)]
+pub(crate) struct Document {
+    prolog: String,
+    root: Element,
+    epilog: String,
+}
+
+impl Document {
+    pub fn parse(content: &str) -> Result<Self> {
+        let (root, range) = Element::read(content, false)?;
+        Ok(Document {
+            prolog: content[..range.start].to_owned(),
+            root,
+            epilog: content[range.end..].to_owned(),
+        })
+    }
+
+    /// Writes `root` laid out as this document is. Elements found in the document keep
+    /// the whitespace around them and the text of numbers that did not change, new ones
+    /// are indented like the rest.
+    pub fn write(&self, mut root: Element) -> String {
+        root.lay_out(Some(&self.root), "\n", self.indentation());
+        format!("{}{}{}", self.prolog, root, self.epilog)
+    }
+
+    /// What each level is indented by, or nothing for a document on a single line.
+    fn indentation(&self) -> Option<&str> {
+        let (spacing, _) = self.root.spacing();
+        let first = spacing.first()?;
+        first.rfind('\n').map(|index| &first[index + 1..])
+    }
+}
+
+impl Element {
+    /// Lays out the children of this element as those of `original`,
+    /// given the whitespace before this element.
+    fn lay_out(&mut self, original: Option<&Element>, space: &str, indentation: Option<&str>) {
+        if let Some(original) = original {
+            self.keep_numbers(original);
+        }
+        if self.elements().next().is_none() {
+            return;
+        }
+
+        // the children of the original by path segment, with the whitespace before them
+        let mut originals = HashMap::new();
+        let mut first = None;
+        let closing = match original {
+            Some(original) if original.elements().next().is_some() => {
+                let (spacing, closing) = original.spacing();
+                first = Some(spacing[0]);
+                originals = original
+                    .child_segments()
+                    .into_iter()
+                    .zip(original.elements().zip(spacing))
+                    .collect();
+                closing
+            }
+            _ => indentation.map_or("", |_| space),
+        };
+        let indented = indentation.map_or_else(String::new, |unit| format!("{}{}", space, unit));
+
+        let mut segments = self.child_segments().into_iter();
+        let mut children = vec![];
+        let mut previous: Option<String> = None;
+        for node in std::mem::take(&mut self.children) {
+            let Node::Element(mut child) = node else {
+                children.push(node);
+                continue;
+            };
+            // new elements are spaced like their previous sibling or the first original one
+            let found = originals.get(&segments.next().unwrap());
+            let space = match (found, previous, first) {
+                (Some((_, space)), _, _) => space.to_string(),
+                (None, Some(previous), _) => previous,
+                (None, None, Some(first)) => first.to_owned(),
+                (None, None, None) => indented.clone(),
+            };
+            child.lay_out(found.map(|(original, _)| *original), &space, indentation);
+            children.push(Node::Text(space.clone()));
+            children.push(Node::Element(child));
+            previous = Some(space);
+        }
+        children.push(Node::Text(closing.to_owned()));
+        self.children = children
+            .into_iter()
+            .filter(|node| !matches!(node, Node::Text(text) if text.is_empty()))
+            .collect();
+    }
+
+    /// The whitespace before each child element, and before the end tag.
+    fn spacing(&self) -> (Vec<&str>, &str) {
+        let mut spacing = vec![];
+        let mut pending = "";
+        for node in self.children.iter() {
+            match node {
+                Node::Text(text) if text.trim().is_empty() => pending = text,
+                Node::Text(_) => pending = "",
+                Node::Element(_) => spacing.push(std::mem::take(&mut pending)),
+            }
+        }
+        (spacing, pending)
+    }
+
+    /// Takes over how `original` wrote the numbers this element has the same values for,
+    /// as `1.50` stays `1.50` even if it would be written as `1.5`.
+    fn keep_numbers(&mut self, original: &Element) {
+        let same_number = |a: &str, b: &str| matches!((a.parse::<f64>(), b.parse::<f64>()), (Ok(a), Ok(b)) if a == b);
+
+        for (key, value) in self.attributes.iter_mut() {
+            if let Some(written) = original.attribute(key) {
+                if same_number(value, written) {
+                    *value = written.to_owned();
+                }
+            }
+        }
+        if let ([Node::Text(value)], [Node::Text(written)]) =
+            (&mut self.children[..], &original.children[..])
+        {
+            if same_number(value, written) {
+                *value = written.clone();
+            }
+        }
+    }
+}
+
+impl Display for Element {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<{}", self.name)?;
+        for (key, value) in self.attributes.iter() {
+            write!(f, " {}=\"{}\"", key, escape(value))?;
+        }
+        if self.children.is_empty() {
+            return write!(f, "/>");
+        }
+        write!(f, ">")?;
+        for child in self.children.iter() {
+            match child {
+                Node::Element(element) => write!(f, "{}", element)?,
+                Node::Text(text) => write!(f, "{}", escape(text))?,
+            }
+        }
+        write!(f, "</{}>", self.name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::project::test::ORIGINAL_CONTENT;
+
+    use super::{Document, Element};
+
+    #[test]
+    fn it_writes_what_it_parses() {
+        let element = Element::parse(ORIGINAL_CONTENT).unwrap();
+        assert_eq!(element.to_string(), ORIGINAL_CONTENT);
+    }
+
+    #[test]
+    fn it_writes_elements_in_the_layout_of_a_document() {
+        let document =
+            Document::parse("<?xml version=\"1.0\"?>\n<a>\n <b x=\"1.50\"/>\n</a>\n").unwrap();
+        let root = Element::parse(r#"<a><b x="1.5"/><c><d/></c></a>"#).unwrap();
+        assert_eq!(
+            document.write(root),
+            "<?xml version=\"1.0\"?>\n<a>\n <b x=\"1.50\"/>\n <c>\n  <d/>\n </c>\n</a>\n"
+        );
+    }
+}